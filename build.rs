@@ -1,4 +1,11 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
   vergen::EmitBuilder::builder().all_build().all_git().emit()?;
+  // Field comments are only part of the GDAL API since 3.7
+  println!("cargo:rustc-check-cfg=cfg(gdal_field_comment)");
+  let gdal_version =
+    std::env::var("DEP_GDAL_VERSION_NUMBER").ok().and_then(|v| v.parse::<u32>().ok());
+  if gdal_version.is_some_and(|v| v >= 3_070_000) {
+    println!("cargo:rustc-cfg=gdal_field_comment");
+  }
   Ok(())
 }
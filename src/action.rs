@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt, string::ToString};
+use std::{collections::HashMap, fmt, path::PathBuf, string::ToString};
 
 use gdal::spatial_ref::SpatialRef;
 use gdal::vector::LayerAccess;
@@ -8,7 +8,17 @@ use serde::{
 };
 use strum::Display;
 
-use crate::data::LayerInfo;
+use crate::data::{
+    crs::{CrsEntry, CrsTarget},
+    diff::{FeatureDiff, FeatureKey, SchemaDiff},
+    export::ExportOptions,
+    extent::Extent,
+    features::{FeatureInfo, FeaturePage},
+    geometries::{GeometrySummary, MapGeometries},
+    raster::RasterInfo,
+    sql::SqlDialect,
+    DatasetInfo, LayerInfo, SpatialFilter,
+};
 
 #[derive(Debug, PartialEq, Clone, Serialize, Display, Deserialize)]
 pub enum Action {
@@ -29,5 +39,108 @@ pub enum Action {
     ScrollRight,
     UpLayer,
     DownLayer,
+    ToggleDataset,
+    SelectLayer(usize, usize),
+    LoadLayer(usize, usize),
+    LayerLoaded(usize, usize, LayerInfo),
     PassLayerInfo(LayerInfo),
+    PassRasterInfo(RasterInfo),
+    EnterInput,
+    ExitInput,
+    NextRow,
+    PreviousRow,
+    NextColumn,
+    PreviousColumn,
+    NextPage,
+    PreviousPage,
+    JumpToFid,
+    ReadFeatures(usize, usize, u64),
+    FindFeature(usize, usize, u64),
+    PassFeaturePage(usize, usize, FeaturePage),
+    FeatureNotFound(u64),
+    ShowFeature,
+    CloseFeature,
+    /// Center the position map on a geometry problem, given in the CRS of the layer
+    LocateProblem(Option<(f64, f64)>),
+    /// Write the located geometry problems to a GeoJSON file
+    ExportProblems,
+    WriteProblems(usize, usize, GeometrySummary, PathBuf),
+    /// Path written to and number of problems written
+    ProblemsWritten(PathBuf, usize),
+    WriteProblemsFailed(String),
+    ReadFeature(usize, usize, u64),
+    PassFeatureInfo(FeatureInfo),
+    /// Read the geometries of a geometry field of a layer for the position map
+    ReadMapGeometries(usize, usize, usize),
+    PassMapGeometries(usize, usize, MapGeometries),
+    NextGeomField,
+    /// Show the CRS definition as WKT1, WKT2_2019, PROJJSON or PROJ string
+    NextSrsFormat,
+    /// Reproject the extent into the next of a few common CRS
+    NextExtentCrs,
+    /// Type the CRS to reproject the extent into
+    TypeExtentCrs,
+    /// Search the CRS to reproject the extent into
+    OpenCrsSearch,
+    SearchCrs(CrsTarget),
+    /// Read the CRS of the PROJ database for the search popup
+    ListCrs,
+    PassCrsList(Vec<CrsEntry>),
+    /// CRS chosen in the search popup, `None` if cancelled
+    CrsChosen(CrsTarget, Option<String>),
+    SelectGeomField(usize),
+    EditFilter,
+    SetAttributeFilter(usize, usize, Option<String>),
+    AttributeFilterSet(usize, usize, Option<String>),
+    AttributeFilterFailed(usize, usize, String),
+    DrawBbox,
+    TypeBbox,
+    /// Show the feature under a cursor moved with the arrows on the position map
+    PickFeature,
+    ClearSpatialFilter,
+    ZoomIn,
+    ZoomOut,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    /// Show the extent of the selected layer on the position map
+    ZoomToLayer,
+    /// Show the extent of every opened dataset on the position map
+    ZoomToAllLayers,
+    ZoomToWorld,
+    /// Extent of every opened dataset as longitude/latitude, `None` if none could be read
+    PassLayersExtent(Option<Extent>),
+    SetSpatialFilter(usize, usize, Option<SpatialFilter>),
+    SpatialFilterSet(usize, usize, Option<SpatialFilter>),
+    OpenSqlConsole,
+    ExecuteSql(usize, String, SqlDialect),
+    /// Result set of a SQL statement, `None` if it returned none
+    SqlResult(Option<DatasetInfo>),
+    SqlFailed(String),
+    OpenExport,
+    Export(usize, usize, ExportOptions),
+    /// Fraction of the export done, from 0 to 1
+    ExportProgress(f64),
+    ExportDone(PathBuf),
+    ExportFailed(String),
+    /// Compare the dataset of the selected layer with the next dataset
+    CompareDatasets,
+    /// Ask for the tolerance of the comparison of two datasets
+    OpenSchemaDiff(usize, usize),
+    /// Compare the layers of two datasets, extents moved by less than the tolerance being
+    /// unchanged
+    DiffSchemas(usize, usize, f64),
+    PassSchemaDiff(SchemaDiff),
+    CloseSchemaDiff,
+    /// Compare the features of the selected layer with its version in the next dataset
+    CompareFeatures,
+    /// Ask for the key pairing the features of two layers, given as dataset and layer index
+    OpenFeatureDiff((usize, usize), (usize, usize)),
+    /// Compare two layers by key, geometries moved by less than the tolerance being unchanged
+    DiffFeatures((usize, usize), (usize, usize), FeatureKey, f64),
+    PassFeatureDiff(FeatureDiff),
+    FeatureDiffFailed(String),
+    /// Close the feature diff, cancelling the comparison if still running
+    CloseFeatureDiff,
 }
@@ -11,18 +11,21 @@ use tokio::sync::mpsc; // 0.17.1
 use crate::{
     action::Action,
     components::{
-        extent::Extent, fields::Fields, fps::FpsCounter, home::Home, layers::LayerList,
-        position_map::PositionMap, srs::Srs, Component, FocusableComponents, FocusableWidget,
+        crs_search::CrsSearch, diff::SchemaDiffView, export::ExportDialog, extent::Extent,
+        feature::FeatureDetail, feature_diff::FeatureDiffView, fields::Fields, fps::FpsCounter,
+        geometries::Geometries, home::Home, layers::LayerList, position_map::PositionMap,
+        raster::Raster, sql::SqlConsole, srs::Srs, table::AttributeTable, Component,
+        FocusableComponents, FocusableWidget,
     },
     config::Config,
-    data,
+    data::{self, worker::Worker},
     mode::Mode,
     tui,
 };
 
 pub struct App {
     pub config: Config,
-    //pub dataset: Dataset,
+    pub datasets: Vec<Dataset>,
     pub components: FocusableComponents, //Vec<Box<dyn FocusableWidget>>,
     pub should_quit: bool,
     pub should_suspend: bool,
@@ -32,16 +35,25 @@ pub struct App {
 }
 
 impl App {
-    pub fn new(dataset: Dataset) -> Result<Self> {
+    pub fn new(config: Config, datasets: Vec<Dataset>) -> Result<Self> {
         //let dataset = data::dataset(path).unwrap();
         let home = Home::new();
         let fps = FpsCounter::default();
-        let layers = LayerList::new(dataset);
-        let srs = Srs::from_layerinfo(&layers.layerinfos[0]);
-        let extent = Extent::from_layerinfo(&layers.layerinfos[0]);
-        let fields = Fields::from_layerinfo(&layers.layerinfos[0]);
-        let position_map = PositionMap::from_layerinfo(&layers.layerinfos[0]);
-        let config = Config::new()?;
+        let layers = LayerList::new(&datasets);
+        let layerinfo = layers.layerinfo().cloned().unwrap_or_default();
+        let srs = Srs::from_layerinfo(&layerinfo);
+        let extent = Extent::from_layerinfo(&layerinfo);
+        let fields = Fields::from_layerinfo(&layerinfo);
+        let geometries = Geometries::from_layerinfo(&layerinfo);
+        let position_map = PositionMap::from_layerinfo(&layerinfo);
+        let raster = Raster::new();
+        let table = AttributeTable::new();
+        let feature = FeatureDetail::new();
+        let sql = SqlConsole::new();
+        let export = ExportDialog::new();
+        let crs_search = CrsSearch::new();
+        let diff = SchemaDiffView::new();
+        let feature_diff = FeatureDiffView::new();
         let mode = Mode::LayerList;
         /*         let focusable_components = FocusableComponents {
             children: vec![
@@ -52,16 +64,25 @@ impl App {
             ],
         }; */
         Ok(Self {
-            //dataset,
+            datasets,
             components: FocusableComponents {
                 children: vec![
                     //Box::new(home),
                     //Box::new(fps),
                     Box::new(layers),
+                    Box::new(geometries),
                     Box::new(srs),
                     Box::new(extent),
                     Box::new(fields),
+                    Box::new(raster),
                     Box::new(position_map),
+                    Box::new(table),
+                    Box::new(feature),
+                    Box::new(diff),
+                    Box::new(feature_diff),
+                    Box::new(sql),
+                    Box::new(export),
+                    Box::new(crs_search),
                 ],
             },
             should_quit: false,
@@ -87,13 +108,21 @@ impl App {
         }
     } */
 
+    fn focused_mode(&self) -> Mode {
+        self.components
+            .children
+            .iter()
+            .find(|c| c.is_focused())
+            .map_or(Mode::Home, |c| c.mode())
+    }
+
     pub async fn run(&mut self) -> Result<()> {
         let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        let mut worker = Worker::spawn(std::mem::take(&mut self.datasets), action_tx.clone());
 
-        let mut tui = tui::Tui::new()?;
+        let mut tui = tui::Tui::new()?.mouse(true);
         //.tick_rate(self.tick_rate)
         //.frame_rate(self.frame_rate);
-        // tui.mouse(true);
         tui.enter()?;
 
         for component in self.components.children.iter_mut() {
@@ -116,8 +145,10 @@ impl App {
                     tui::Event::Render => action_tx.send(Action::Render)?,
                     tui::Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
                     tui::Event::Key(key) => {
-                        // If key is tab we always switch mode
-                        if key.code == KeyCode::Tab {
+                        if self.mode == Mode::Input {
+                            // The component reading text gets the key through `handle_events`
+                        } else if key.code == KeyCode::Tab {
+                            // If key is tab we always switch mode
                             action_tx.send(Action::NextFocusableMode)?;
                         } else if key.code == KeyCode::BackTab {
                             action_tx.send(Action::PreviousFocusableMode)?;
@@ -141,6 +172,10 @@ impl App {
                     _ => {}
                 }
                 for component in self.components.children.iter_mut() {
+                    // Mouse events go to the focused panel, never to those under an overlay
+                    if matches!(e, tui::Event::Mouse(_)) && !component.is_focused() {
+                        continue;
+                    }
                     if let Some(action) = component.handle_events(Some(e.clone()))? {
                         action_tx.send(action)?;
                     }
@@ -158,8 +193,43 @@ impl App {
                     Action::Quit => self.should_quit = true,
                     Action::Suspend => self.should_suspend = true,
                     Action::Resume => self.should_suspend = false,
-                    Action::NextFocusableMode => self.components.focus_next(),
-                    Action::PreviousFocusableMode => self.components.focus_previous(),
+                    Action::NextFocusableMode => {
+                        self.components.focus_next();
+                        self.mode = self.focused_mode();
+                    }
+                    Action::PreviousFocusableMode => {
+                        self.components.focus_previous();
+                        self.mode = self.focused_mode();
+                    }
+                    Action::EnterInput => self.mode = Mode::Input,
+                    Action::ExitInput => self.mode = self.focused_mode(),
+                    Action::LoadLayer(d, l) => worker.load_layer(d, l),
+                    Action::ReadFeatures(d, l, offset) => worker.read_features(d, l, offset),
+                    Action::FindFeature(d, l, fid) => worker.find_feature(d, l, fid),
+                    Action::ReadFeature(d, l, fid) => worker.read_feature(d, l, fid),
+                    Action::ReadMapGeometries(d, l, g) => worker.read_map_geometries(d, l, g),
+                    Action::SetAttributeFilter(d, l, ref filter) => {
+                        worker.set_attribute_filter(d, l, filter.clone())
+                    }
+                    Action::DiffSchemas(a, b, tolerance) => worker.diff_schemas(a, b, tolerance),
+                    Action::ZoomToAllLayers => worker.layers_extent(),
+                    Action::ListCrs => worker.list_crs(),
+                    Action::DiffFeatures(a, b, ref key, tolerance) => {
+                        worker.diff_features(a, b, key.clone(), tolerance)
+                    }
+                    Action::CloseFeatureDiff => worker.cancel_diff(),
+                    Action::WriteProblems(d, l, ref summary, ref path) => {
+                        worker.write_problems(d, l, summary.clone(), path.clone())
+                    }
+                    Action::Export(d, l, ref options) => worker.export(d, l, options.clone()),
+                    Action::ExecuteSql(d, ref query, dialect) => {
+                        worker.execute_sql(d, query.clone(), dialect)
+                    }
+                    Action::SetSpatialFilter(d, l, ref filter) => {
+                        worker.set_spatial_filter(d, l, filter.clone())
+                    }
+                    // Another leaf is displayed, whatever is still loading is not needed anymore
+                    Action::PassLayerInfo(_) | Action::PassRasterInfo(_) => worker.cancel(),
                     Action::Resize(w, h) => {
                         tui.resize(Rect::new(0, 0, w, h))?;
                         tui.draw(|f| {
@@ -192,14 +262,24 @@ impl App {
                         action_tx.send(action)?
                     };
                 }
+                // A panel hidden while focused hands the focus back to the one before it
+                if self.mode != Mode::Input
+                    && self
+                        .components
+                        .children
+                        .iter()
+                        .any(|c| c.is_focused() && !c.can_focus())
+                {
+                    self.components.focus_previous();
+                    self.mode = self.focused_mode();
+                }
             }
             if self.should_suspend {
                 tui.suspend()?;
                 action_tx.send(Action::Resume)?;
-                tui = tui::Tui::new()?;
+                tui = tui::Tui::new()?.mouse(true);
                 //.tick_rate(self.tick_rate)
                 //.frame_rate(self.frame_rate);
-                // tui.mouse(true);
                 tui.enter()?;
             } else if self.should_quit {
                 tui.stop()?;
@@ -1,12 +1,27 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 
-use crate::utils::version;
+use crate::{
+  data::{diff::DEFAULT_TOLERANCE, GdalOptions},
+  utils::version,
+};
 
 #[derive(Parser, Debug)]
-#[command(author, version = version(), about)]
+#[command(
+  author,
+  version = version(),
+  about,
+  args_conflicts_with_subcommands = true,
+  subcommand_negates_reqs = true
+)]
 pub struct Cli {
+  #[command(subcommand)]
+  pub command: Option<Command>,
+
+  #[arg(value_name = "PATH", help = "Dataset(s) to open", required = true, num_args = 1..)]
+  pub datasets: Vec<PathBuf>,
+
   #[arg(short, long, value_name = "FLOAT", help = "Tick rate, i.e. number of ticks per second", default_value_t = 1.0)]
   pub tick_rate: f64,
 
@@ -18,4 +33,125 @@ pub struct Cli {
     default_value_t = 4.0
   )]
   pub frame_rate: f64,
+
+  #[arg(long = "oo", value_name = "KEY=VALUE", help = "Dataset open option", value_parser = parse_key_value, global = true)]
+  pub open_options: Vec<String>,
+
+  #[arg(long = "driver", value_name = "NAME", help = "Only try this driver to open the datasets", global = true)]
+  pub drivers: Vec<String>,
+
+  #[arg(long = "config", value_names = ["KEY", "VALUE"], num_args = 2, help = "GDAL configuration option", global = true)]
+  pub config_options: Vec<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+  /// Print the layers of a dataset without starting the interface
+  Info {
+    #[arg(value_name = "PATH", help = "Dataset to describe")]
+    path: PathBuf,
+
+    #[arg(short, long, value_enum, default_value_t = InfoFormat::Text)]
+    format: InfoFormat,
+
+    #[arg(short, long = "layer", value_name = "NAME", help = "Only describe this layer")]
+    layers: Vec<String>,
+
+    #[arg(long, help = "Read every feature for field statistics and geometry problems (JSON and YAML)")]
+    scan: bool,
+  },
+  /// Print the schema differences between two datasets as JSON
+  Diff {
+    #[arg(value_name = "BEFORE")]
+    before: PathBuf,
+
+    #[arg(value_name = "AFTER")]
+    after: PathBuf,
+
+    #[arg(long, value_name = "FLOAT", help = "Largest extent change ignored", default_value_t = DEFAULT_TOLERANCE)]
+    tolerance: f64,
+
+    #[arg(short, long = "layer", value_name = "NAME", help = "Only compare this layer")]
+    layers: Vec<String>,
+  },
+}
+
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InfoFormat {
+  Json,
+  Yaml,
+  Markdown,
+  /// Layout of ogrinfo
+  #[default]
+  Text,
+}
+
+impl Cli {
+  pub fn gdal_options(&self) -> GdalOptions {
+    GdalOptions {
+      open_options: self.open_options.clone(),
+      drivers: self.drivers.clone(),
+      config_options: self.config_options.chunks(2).map(|kv| (kv[0].clone(), kv[1].clone())).collect(),
+    }
+  }
+}
+
+fn parse_key_value(s: &str) -> Result<String, String> {
+  match s.split_once('=') {
+    Some((key, _)) if !key.is_empty() => Ok(s.to_string()),
+    _ => Err(format!("expected KEY=VALUE, got `{s}`")),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_gdal_options() {
+    let cli = Cli::parse_from([
+      "vivitui",
+      "a.csv",
+      "--oo",
+      "X_POSSIBLE_NAMES=lon",
+      "--driver",
+      "CSV",
+      "--config",
+      "OGR_SQLITE_CACHE",
+      "512",
+      "--config",
+      "CPL_DEBUG",
+      "ON",
+    ]);
+    let options = cli.gdal_options();
+    assert_eq!(options.open_options, vec!["X_POSSIBLE_NAMES=lon"]);
+    assert_eq!(options.drivers, vec!["CSV"]);
+    assert_eq!(options.config_options.get("OGR_SQLITE_CACHE"), Some(&"512".to_string()));
+    assert_eq!(options.config_options.get("CPL_DEBUG"), Some(&"ON".to_string()));
+  }
+
+  #[test]
+  fn test_info() {
+    let cli =
+      Cli::parse_from(["vivitui", "info", "a.gpkg", "--format", "json", "-l", "roads", "--oo", "LIST_ALL_TABLES=YES"]);
+    let Some(Command::Info { path, format, layers, scan }) = &cli.command else {
+      panic!("expected the info subcommand");
+    };
+    assert_eq!(path, &PathBuf::from("a.gpkg"));
+    assert_eq!(*format, InfoFormat::Json);
+    assert_eq!(layers, &vec!["roads"]);
+    assert!(!scan);
+    assert_eq!(cli.gdal_options().open_options, vec!["LIST_ALL_TABLES=YES"]);
+    assert!(Cli::try_parse_from(["vivitui"]).is_err());
+  }
+
+  #[test]
+  fn test_parse_key_value() {
+    assert!(parse_key_value("KEY=VALUE").is_ok());
+    assert!(parse_key_value("KEY=").is_ok());
+    assert!(parse_key_value("KEY").is_err());
+    assert!(parse_key_value("=VALUE").is_err());
+  }
 }
@@ -8,16 +8,26 @@ use tokio::sync::mpsc::UnboundedSender;
 use crate::{
     action::Action,
     config::Config,
+    mode::Mode,
     tui::{Event, Frame},
 };
 
+pub mod crs_search;
+pub mod diff;
+pub mod export;
 pub mod extent;
+pub mod feature;
+pub mod feature_diff;
 pub mod fields;
 pub mod fps;
+pub mod geometries;
 pub mod home;
 pub mod layers;
 pub mod position_map;
+pub mod raster;
+pub mod sql;
 pub mod srs;
+pub mod table;
 
 /// `Component` is a trait that represents a visual and interactive element of the user interface.
 /// Implementors of this trait can be registered with the main application loop and will be able to receive events,
@@ -131,6 +141,11 @@ pub trait Component {
 }
 
 pub trait FocusableWidget: Component + Focus {
+    /// Keybindings mode while the widget has the focus.
+    fn mode(&self) -> Mode {
+        Mode::Home
+    }
+
     fn boxed(self) -> Box<dyn FocusableWidget>
     where
         Self: 'static + Sized,
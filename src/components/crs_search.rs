@@ -0,0 +1,236 @@
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use focusable::Focus;
+use ratatui::{prelude::*, widgets::*};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{Component, FocusableWidget};
+use crate::{
+    action::Action,
+    data::crs::{self, CrsEntry, CrsTarget},
+    mode::Mode,
+    tui::Frame,
+};
+
+/// Popup searching the CRS of the PROJ database, offline, for the panel that opened it.
+///
+/// Like the SQL console, it has no focus of its own and reads keys in input mode.
+#[derive(Debug, Default, Clone, Focus)]
+pub struct CrsSearch {
+    command_tx: Option<UnboundedSender<Action>>,
+    /// Panel waiting for a CRS, set while the popup is open
+    target: Option<CrsTarget>,
+    /// Read from the database by the GDAL thread the first time the popup opens
+    entries: Vec<CrsEntry>,
+    is_loading: bool,
+    query: String,
+    /// Indices of the entries matching the query
+    matches: Vec<usize>,
+    state: ListState,
+}
+
+impl CrsSearch {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn filter(&mut self) {
+        self.matches = crs::search(&self.entries, &self.query);
+        self.state.select((!self.matches.is_empty()).then_some(0));
+    }
+
+    fn selected(&self) -> Option<&CrsEntry> {
+        let i = *self.matches.get(self.state.selected()?)?;
+        self.entries.get(i)
+    }
+
+    /// Close the popup, handing `crs` to the panel that asked for it.
+    fn close(&mut self, crs: Option<String>) -> Option<Action> {
+        let target = self.target.take()?;
+        Some(Action::CrsChosen(target, crs))
+    }
+
+    fn details(entry: &CrsEntry) -> Vec<Line<'_>> {
+        let line = |label: &'static str, value: String| {
+            Line::from(vec![
+                Span::from(format!("{label}: ")).fg(Color::LightCyan),
+                Span::from(value),
+            ])
+        };
+        let mut lines = vec![
+            Line::from(entry.name.as_str()).bold(),
+            line("code", entry.id()),
+            line("type", entry.kind.clone()),
+        ];
+        if let Some(method) = &entry.projection_method {
+            lines.push(line("projection", method.clone()));
+        }
+        if let Some(area) = &entry.area {
+            lines.push(line("area of use", area.clone()));
+        }
+        if let Some([west, south, east, north]) = entry.bbox {
+            lines.push(line("bbox", format!("{west}, {south}, {east}, {north}")));
+        }
+        if entry.deprecated {
+            lines.push(Line::from("deprecated").fg(Color::LightRed));
+        }
+        lines
+    }
+}
+
+impl FocusableWidget for CrsSearch {
+    fn mode(&self) -> Mode {
+        Mode::Input
+    }
+}
+
+impl Component for CrsSearch {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.command_tx = Some(tx);
+        Ok(())
+    }
+
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if self.target.is_none() {
+            return Ok(None);
+        }
+        match key.code {
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.filter();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.filter();
+            }
+            KeyCode::Down if !self.matches.is_empty() => {
+                let last = self.matches.len() - 1;
+                self.state
+                    .select(Some(self.state.selected().map_or(0, |i| (i + 1).min(last))));
+            }
+            KeyCode::Up => {
+                self.state
+                    .select(self.state.selected().map(|i| i.saturating_sub(1)));
+            }
+            KeyCode::Enter => {
+                let crs = self.selected().map(|e| e.id());
+                if crs.is_some() {
+                    return Ok(self.close(crs));
+                }
+            }
+            KeyCode::Esc => return Ok(self.close(None)),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::SearchCrs(target) => {
+                if let (true, false, Some(tx)) =
+                    (self.entries.is_empty(), self.is_loading, &self.command_tx)
+                {
+                    self.is_loading = true;
+                    tx.send(Action::ListCrs)?;
+                }
+                self.target = Some(target);
+                self.filter();
+                return Ok(Some(Action::EnterInput));
+            }
+            Action::PassCrsList(entries) => {
+                self.is_loading = false;
+                self.entries = entries;
+                self.filter();
+            }
+            // The export dialog stays in input mode
+            Action::CrsChosen(CrsTarget::Extent, _) => return Ok(Some(Action::ExitInput)),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        if self.target.is_none() {
+            return Ok(());
+        }
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Percentage(15),
+                Constraint::Percentage(70),
+                Constraint::Fill(1),
+            ])
+            .split(rect);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Percentage(10),
+                Constraint::Percentage(80),
+                Constraint::Fill(1),
+            ])
+            .split(rows[1]);
+
+        let rect = columns[1];
+
+        let title = format!(
+            "Find CRS: {}▏ ({} of {})",
+            self.query,
+            self.matches.len(),
+            self.entries.len()
+        );
+        let block = Block::default()
+            .title(block::Title::from(title).alignment(Alignment::Left))
+            .title(
+                block::Title::from("<enter> choose  <esc> cancel")
+                    .alignment(Alignment::Right)
+                    .position(block::Position::Bottom),
+            )
+            .borders(Borders::ALL)
+            .border_set(symbols::border::DOUBLE);
+
+        let panel_rects = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(60), Constraint::Fill(1)])
+            .split(block.inner(rect));
+
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .map(|i| {
+                let e = &self.entries[*i];
+                let item = ListItem::new(Line::from(vec![
+                    Span::from(format!("{} ", e.id())).fg(Color::LightCyan),
+                    Span::from(e.name.as_str()),
+                ]));
+                if e.deprecated {
+                    item.fg(Color::DarkGray)
+                } else {
+                    item
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_symbol(">> ")
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let details = match self.selected() {
+            Some(entry) => Paragraph::new(Self::details(entry)),
+            None if self.is_loading => {
+                Paragraph::new(Line::from("Reading the PROJ database…").italic())
+            }
+            None if self.entries.is_empty() => {
+                Paragraph::new(Line::from("PROJ database not found").fg(Color::LightRed))
+            }
+            None => Paragraph::new(Line::from("No match").italic()),
+        }
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::LEFT));
+
+        f.render_widget(Clear, rect);
+        f.render_widget(block, rect);
+        f.render_widget(details, panel_rects[1]);
+        f.render_stateful_widget(list, panel_rects[0], &mut self.state);
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use focusable::Focus;
+use ratatui::{prelude::*, widgets::*};
+
+use super::{Component, FocusableWidget};
+use crate::{
+    action::Action,
+    data::diff::{parse_tolerance, LayerDiff, SchemaDiff, DEFAULT_TOLERANCE},
+    mode::Mode,
+    tui::Frame,
+};
+
+/// Schema differences between two datasets, drawn over the right half of the screen while open.
+#[derive(Debug, Default, Clone)]
+pub struct SchemaDiffView {
+    /// Datasets to compare while their tolerance is being typed
+    datasets: Option<(usize, usize)>,
+    /// Largest extent move ignored, kept from one comparison to the next
+    tolerance_input: String,
+    /// Why the typed tolerance was refused
+    input_error: Option<String>,
+    pub diff: Option<SchemaDiff>,
+    pub scroll: u16,
+    pub is_focused: bool,
+    pub is_loading: bool,
+}
+
+fn added(text: String) -> Line<'static> {
+    Line::from(format!("+ {text}")).fg(Color::LightGreen)
+}
+
+fn removed(text: String) -> Line<'static> {
+    Line::from(format!("- {text}")).fg(Color::LightRed)
+}
+
+fn changed(text: String) -> Line<'static> {
+    Line::from(format!("~ {text}")).fg(Color::LightYellow)
+}
+
+impl SchemaDiffView {
+    pub fn new() -> Self {
+        Self {
+            tolerance_input: format!("{DEFAULT_TOLERANCE:e}"),
+            ..Default::default()
+        }
+    }
+
+    fn layer_lines(layer: &LayerDiff) -> Vec<Line<'static>> {
+        let title = match &layer.renamed_to {
+            Some(name) => format!("{} → {name}", layer.name),
+            None => layer.name.clone(),
+        };
+        let mut lines = vec![Line::from(title).bold().fg(Color::LightCyan)];
+        for f in &layer.added_fields {
+            lines.push(added(format!("field {}: {}", f.name, f.type_name)));
+        }
+        for f in &layer.removed_fields {
+            lines.push(removed(format!("field {}: {}", f.name, f.type_name)));
+        }
+        for f in &layer.changed_fields {
+            lines.push(changed(format!(
+                "field {}: {} → {}",
+                f.name, f.definition.before, f.definition.after
+            )));
+        }
+        if let Some(srs) = &layer.srs {
+            lines.push(changed(format!(
+                "CRS: {} → {}",
+                srs.before.name, srs.after.name
+            )));
+        }
+        if let Some(extent) = &layer.extent {
+            let (a, b) = (&extent.before, &extent.after);
+            lines.push(changed(format!(
+                "extent: {} {} {} {} → {} {} {} {}",
+                a.xmin, a.ymin, a.xmax, a.ymax, b.xmin, b.ymin, b.xmax, b.ymax
+            )));
+        }
+        let count = &layer.feature_count;
+        if count.before != count.after {
+            let delta = count.after as i64 - count.before as i64;
+            lines.push(changed(format!(
+                "features: {} → {} ({delta:+})",
+                count.before, count.after
+            )));
+        }
+        lines
+    }
+
+    fn prompt_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(format!("Tolerance: {}▏", self.tolerance_input)).fg(Color::LightYellow),
+            Line::from("Largest extent move ignored, in layer units").fg(Color::DarkGray),
+            Line::default(),
+            Line::from("<enter> compare  <esc> cancel").fg(Color::DarkGray),
+        ];
+        if let Some(e) = &self.input_error {
+            lines.push(Line::from(e.clone()).fg(Color::LightRed));
+        }
+        lines
+    }
+
+    fn lines(diff: &SchemaDiff) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            removed(diff.before.clone()),
+            added(diff.after.clone()),
+            Line::default(),
+        ];
+        if diff.is_empty() {
+            lines.push(Line::from("No differences").italic());
+            return lines;
+        }
+        for name in &diff.added_layers {
+            lines.push(added(format!("layer {name}")));
+        }
+        for name in &diff.removed_layers {
+            lines.push(removed(format!("layer {name}")));
+        }
+        for layer in &diff.layers {
+            lines.push(Line::default());
+            lines.extend(Self::layer_lines(layer));
+        }
+        lines
+    }
+}
+
+// Focusable while comparing or showing a diff, once the tolerance is typed
+impl Focus for SchemaDiffView {
+    fn can_focus(&self) -> bool {
+        (self.diff.is_some() || self.is_loading) && self.datasets.is_none()
+    }
+
+    fn is_focused(&self) -> bool {
+        self.is_focused
+    }
+
+    fn focus(&mut self) {
+        self.is_focused = true;
+    }
+
+    fn blur(&mut self) {
+        self.is_focused = false;
+    }
+}
+
+impl FocusableWidget for SchemaDiffView {
+    fn mode(&self) -> Mode {
+        Mode::SchemaDiff
+    }
+}
+
+impl Component for SchemaDiffView {
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let Some((before, after)) = self.datasets else {
+            return Ok(None);
+        };
+        match key.code {
+            KeyCode::Char(c) => self.tolerance_input.push(c),
+            KeyCode::Backspace => {
+                self.tolerance_input.pop();
+            }
+            KeyCode::Enter => match parse_tolerance(&self.tolerance_input) {
+                Ok(tolerance) => {
+                    self.datasets = None;
+                    self.is_loading = true;
+                    return Ok(Some(Action::DiffSchemas(before, after, tolerance)));
+                }
+                Err(e) => self.input_error = Some(e),
+            },
+            KeyCode::Esc => {
+                self.datasets = None;
+                return Ok(Some(Action::ExitInput));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::OpenSchemaDiff(before, after) => {
+                self.datasets = Some((before, after));
+                self.input_error = None;
+                self.diff = None;
+                return Ok(Some(Action::EnterInput));
+            }
+            Action::DiffSchemas(..) => return Ok(Some(Action::ExitInput)),
+            Action::PassSchemaDiff(diff) => {
+                self.is_loading = false;
+                self.diff = Some(diff);
+                self.scroll = 0;
+            }
+            Action::CloseSchemaDiff => self.diff = None,
+            Action::ScrollDown if self.is_focused => self.scroll = self.scroll.saturating_add(1),
+            Action::ScrollUp if self.is_focused => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let lines = match &self.diff {
+            _ if self.datasets.is_some() => self.prompt_lines(),
+            Some(diff) => Self::lines(diff),
+            None if self.is_loading => vec![Line::from("Comparing…").italic()],
+            None => return Ok(()),
+        };
+
+        let rects = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
+            .split(rect);
+
+        let rect = rects[1];
+
+        let mut block = Block::default()
+            .title(block::Title::from("Schema diff").alignment(Alignment::Left))
+            .borders(Borders::ALL);
+
+        if self.is_focused || self.datasets.is_some() {
+            block = block.border_set(symbols::border::DOUBLE);
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+            .block(block);
+
+        f.render_widget(Clear, rect);
+        f.render_widget(paragraph, rect);
+        Ok(())
+    }
+}
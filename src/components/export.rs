@@ -0,0 +1,309 @@
+use std::path::PathBuf;
+
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use focusable::Focus;
+use ratatui::{prelude::*, widgets::*};
+
+use super::{Component, FocusableWidget};
+use crate::{
+    action::Action,
+    data::{
+        crs::CrsTarget,
+        export::{self, ExportDriver, ExportOptions},
+    },
+    mode::Mode,
+    tui::Frame,
+};
+
+/// Input of the export dialog being edited.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ExportField {
+    #[default]
+    Driver,
+    Path,
+    Crs,
+}
+
+impl ExportField {
+    fn next(self) -> Self {
+        match self {
+            ExportField::Driver => ExportField::Path,
+            ExportField::Path => ExportField::Crs,
+            ExportField::Crs => ExportField::Driver,
+        }
+    }
+}
+
+/// Dialog writing the selected layer, filters applied, to a new file with any vector driver able
+/// to create one. Drawn over the attribute table while open.
+///
+/// Like the SQL console, it has no focus of its own and reads keys in input mode.
+#[derive(Debug, Default, Clone, Focus)]
+pub struct ExportDialog {
+    is_open: bool,
+    drivers: Vec<ExportDriver>,
+    state: ListState,
+    path: String,
+    crs: String,
+    /// Whether the output is labelled with `crs` rather than reprojected
+    assign_crs: bool,
+    /// Set while the CRS search popup reads the keys
+    searching_crs: bool,
+    field: ExportField,
+    /// Dataset and layer index of the selected layer
+    layer: Option<(usize, usize)>,
+    layer_name: String,
+    /// Fraction written while an export runs
+    progress: Option<f64>,
+    /// Outcome of the last export, and whether it is an error
+    message: Option<(String, bool)>,
+}
+
+impl ExportDialog {
+    pub fn new() -> Self {
+        let drivers = export::drivers();
+        let mut state = ListState::default();
+        state.select(drivers.iter().position(|d| d.name == "GPKG").or(Some(0)));
+        Self {
+            drivers,
+            state,
+            ..Default::default()
+        }
+    }
+
+    fn driver(&self) -> Option<&ExportDriver> {
+        self.drivers.get(self.state.selected()?)
+    }
+
+    /// File name of the layer with the extension of the selected driver.
+    fn default_path(&self) -> String {
+        match self.driver().and_then(|d| d.extension.as_ref()) {
+            Some(extension) => format!("{}.{extension}", self.layer_name),
+            None => self.layer_name.clone(),
+        }
+    }
+
+    fn select_driver(&mut self, index: usize) {
+        // Follow the driver with the extension unless the user typed a path
+        let follow = self.path.is_empty() || self.path == self.default_path();
+        self.state.select(Some(index));
+        if follow {
+            self.path = self.default_path();
+        }
+    }
+
+    fn options(&self) -> Result<ExportOptions, String> {
+        let driver = self.driver().ok_or("No driver selected")?;
+        if self.path.trim().is_empty() {
+            return Err("No output path".into());
+        }
+        let crs = match self.crs.trim() {
+            "" => None,
+            crs => Some(crs.to_string()),
+        };
+        Ok(ExportOptions {
+            driver: driver.name.clone(),
+            path: PathBuf::from(self.path.trim()),
+            crs,
+            assign_crs: self.assign_crs,
+        })
+    }
+}
+
+impl FocusableWidget for ExportDialog {
+    fn mode(&self) -> Mode {
+        Mode::Input
+    }
+}
+
+impl Component for ExportDialog {
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if !self.is_open || self.searching_crs {
+            return Ok(None);
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('f') => {
+                    self.searching_crs = true;
+                    return Ok(Some(Action::SearchCrs(CrsTarget::Export)));
+                }
+                KeyCode::Char('a') => self.assign_crs = !self.assign_crs,
+                _ => {}
+            }
+            return Ok(None);
+        }
+        match (self.field, key.code) {
+            (_, KeyCode::Esc) => {
+                self.is_open = false;
+                return Ok(Some(Action::ExitInput));
+            }
+            (_, KeyCode::Tab) => self.field = self.field.next(),
+            (_, KeyCode::Enter) if self.progress.is_none() => match (self.options(), self.layer) {
+                (Ok(options), Some((d, l))) => {
+                    self.progress = Some(0.);
+                    self.message = None;
+                    return Ok(Some(Action::Export(d, l, options)));
+                }
+                (Err(e), _) => self.message = Some((e, true)),
+                (_, None) => {}
+            },
+            (ExportField::Driver, KeyCode::Down) if !self.drivers.is_empty() => {
+                let last = self.drivers.len() - 1;
+                self.select_driver(self.state.selected().map_or(0, |i| (i + 1).min(last)));
+            }
+            (ExportField::Driver, KeyCode::Up) if !self.drivers.is_empty() => {
+                self.select_driver(self.state.selected().map_or(0, |i| i.saturating_sub(1)));
+            }
+            (ExportField::Path, KeyCode::Char(c)) => self.path.push(c),
+            (ExportField::Path, KeyCode::Backspace) => {
+                self.path.pop();
+            }
+            (ExportField::Crs, KeyCode::Char(c)) => self.crs.push(c),
+            (ExportField::Crs, KeyCode::Backspace) => {
+                self.crs.pop();
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::SelectLayer(d, l) => self.layer = Some((d, l)),
+            Action::PassLayerInfo(li) => self.layer_name = li.name,
+            Action::PassRasterInfo(_) => self.layer = None,
+            Action::OpenExport if self.layer.is_some() => {
+                self.is_open = true;
+                self.field = ExportField::Driver;
+                self.message = None;
+                self.path = self.default_path();
+                return Ok(Some(Action::EnterInput));
+            }
+            Action::CrsChosen(CrsTarget::Export, crs) => {
+                self.searching_crs = false;
+                if let Some(crs) = crs {
+                    self.crs = crs;
+                    self.field = ExportField::Crs;
+                }
+            }
+            Action::ExportProgress(p) => self.progress = Some(p),
+            Action::ExportDone(path) => {
+                self.progress = None;
+                self.message = Some((format!("Wrote {}", path.display()), false));
+            }
+            Action::ExportFailed(e) => {
+                self.progress = None;
+                self.message = Some((e, true));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        if !self.is_open {
+            return Ok(());
+        }
+
+        let rects = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
+            .split(rect);
+
+        let inner_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
+            .split(rects[1]);
+
+        let rect = inner_rects[1];
+
+        let block = Block::default()
+            .title(
+                block::Title::from(format!("Export {}", self.layer_name))
+                    .alignment(Alignment::Left),
+            )
+            .borders(Borders::ALL)
+            .border_set(symbols::border::DOUBLE);
+
+        let panel_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Min(0),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .split(block.inner(rect));
+
+        let label = |field: ExportField, name: &'static str| {
+            let span = Span::from(name);
+            if self.field == field {
+                span.fg(Color::LightYellow).bold()
+            } else {
+                span.fg(Color::LightCyan)
+            }
+        };
+        let cursor = |field: ExportField| if self.field == field { "▏" } else { "" };
+
+        let items: Vec<ListItem> = self
+            .drivers
+            .iter()
+            .map(|d| ListItem::new(d.name.as_str()))
+            .collect();
+        let drivers = List::new(items)
+            .block(
+                Block::default()
+                    .title(Line::from(label(ExportField::Driver, "Driver")))
+                    .borders(Borders::TOP),
+            )
+            .highlight_symbol(">> ")
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let path = Line::from(vec![
+            label(ExportField::Path, "Path: "),
+            Span::from(format!("{}{}", self.path, cursor(ExportField::Path))),
+        ]);
+        let crs = Line::from(vec![
+            label(
+                ExportField::Crs,
+                if self.assign_crs {
+                    "Assign CRS: "
+                } else {
+                    "Reproject to CRS: "
+                },
+            ),
+            Span::from(format!("{}{}", self.crs, cursor(ExportField::Crs))),
+        ]);
+        let hint = Line::from(
+            "<tab> next field  <up>/<down> driver  <ctrl-f> find CRS  <ctrl-a> assign/reproject  \
+             <enter> export  <esc> close",
+        )
+        .fg(Color::DarkGray);
+
+        f.render_widget(Clear, rect);
+        f.render_widget(block, rect);
+        f.render_stateful_widget(drivers, panel_rects[0], &mut self.state);
+        f.render_widget(path, panel_rects[1]);
+        f.render_widget(crs, panel_rects[2]);
+        match (self.progress, &self.message) {
+            (Some(p), _) => f.render_widget(
+                Gauge::default()
+                    .gauge_style(Style::default().fg(Color::LightGreen))
+                    .ratio(p.clamp(0., 1.)),
+                panel_rects[3],
+            ),
+            (None, Some((message, is_error))) => {
+                let color = if *is_error {
+                    Color::LightRed
+                } else {
+                    Color::LightGreen
+                };
+                f.render_widget(Line::from(message.as_str()).fg(color), panel_rects[3]);
+            }
+            (None, None) => {}
+        }
+        f.render_widget(hint, panel_rects[4]);
+        Ok(())
+    }
+}
@@ -1,45 +1,199 @@
 use std::default;
 
 use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
 use focusable::Focus;
-use gdal::vector::{Layer, LayerAccess};
+use gdal::{
+    spatial_ref::SpatialRef,
+    vector::{Layer, LayerAccess},
+};
 use layout::Size;
 use ratatui::{prelude::*, widgets::*};
 use tui_scrollview::{self, ScrollView, ScrollViewState};
 
 use super::{Component, FocusableWidget};
-use crate::{action::Action, data::LayerInfo, tui::Frame};
+use crate::{
+    action::Action,
+    data::{
+        self,
+        crs::CrsTarget,
+        srs::{self, Srs},
+        GeomFieldInfo, LayerInfo,
+    },
+    mode::Mode,
+    tui::Frame,
+};
 
 #[derive(Debug, Default, Clone, Focus)]
 pub struct Extent {
-    pub xmin: f64,
-    pub xmax: f64,
-    pub ymin: f64,
-    pub ymax: f64,
+    /// `None` when there is no geometry to bound
+    pub extent: Option<data::extent::Extent>,
+    pub geom_fields: Vec<GeomFieldInfo>,
+    /// Index of the geometry field shown
+    pub geom_field: usize,
+    /// CRS of the extent shown
+    srs: Srs,
+    /// CRS the extent is also shown in, as typed by the user
+    target: Option<String>,
+    /// Name of the target CRS and extent in it, or why it could not be computed
+    reprojected: Option<Result<(String, data::extent::Extent), String>>,
+    /// Target CRS being typed by the user
+    target_input: Option<String>,
     pub is_focused: bool,
+    pub is_loading: bool,
 }
 
 impl Extent {
     pub fn from_layerinfo(li: &LayerInfo) -> Self {
         Self {
-            xmin: li.extent.xmin,
-            xmax: li.extent.xmax,
-            ymin: li.extent.ymin,
-            ymax: li.extent.ymax,
-            is_focused: false,
+            extent: li.extent.clone(),
+            geom_fields: li.geom_fields.clone(),
+            geom_field: 0,
+            srs: li.srs.clone(),
+            ..Default::default()
         }
     }
 }
 
-impl FocusableWidget for Extent {}
+/// CRS cycled through by `NextExtentCrs`.
+const TARGET_CRS: [&str; 2] = ["EPSG:4326", "EPSG:3857"];
+
+fn extent_lines(extent: &data::extent::Extent) -> Vec<Line<'static>> {
+    vec![
+        Line::from(format!("xmin: {}", extent.xmin)),
+        Line::from(format!("xmax: {}", extent.xmax)),
+        Line::from(format!("ymin: {}", extent.ymin)),
+        Line::from(format!("ymax: {}", extent.ymax)),
+    ]
+}
+
+impl Extent {
+    fn set_extent(&mut self, extent: Option<&data::extent::Extent>, srs: &Srs) {
+        self.extent = extent.cloned();
+        self.srs = srs.clone();
+        self.reproject();
+    }
+
+    /// Compute the extent in the target CRS, if any.
+    fn reproject(&mut self) {
+        self.reprojected = self.target.as_ref().map(|target| {
+            let Some(extent) = &self.extent else {
+                return Err("No extent to reproject".into());
+            };
+            if self.srs.wkt.is_empty() {
+                return Err("No CRS to reproject from".into());
+            }
+            let dsr =
+                srs::from_user_input(target).map_err(|e| format!("Unknown CRS '{target}': {e}"))?;
+            let reprojected = self
+                .srs
+                .spatial_ref()
+                .and_then(|ssr| extent.reproject(&ssr, &dsr))
+                .map_err(|e| format!("Reprojection failed: {e}"))?;
+            Ok((dsr.name().unwrap_or_else(|_| target.clone()), reprojected))
+        });
+    }
+
+    fn set_target(&mut self, target: Option<String>) {
+        self.target = target;
+        self.reproject();
+    }
+
+    fn title(&self) -> String {
+        let mut title = String::from("Extent");
+        if self.geom_fields.len() > 1 {
+            let name = &self.geom_fields[self.geom_field].name;
+            let (i, n) = (self.geom_field + 1, self.geom_fields.len());
+            title.push_str(&format!(" [{name} {i}/{n}]"));
+        }
+        if self.is_loading {
+            title.push_str(" (loading…)");
+        }
+        if let Some(input) = &self.target_input {
+            title.push_str(&format!(" CRS: {input}▏"));
+        } else if let Some(Ok((name, _))) = &self.reprojected {
+            title.push_str(&format!(" | {name}"));
+        }
+        title
+    }
+}
+
+impl FocusableWidget for Extent {
+    fn mode(&self) -> Mode {
+        Mode::Extent
+    }
+}
 
 impl Component for Extent {
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let Some(input) = &mut self.target_input else {
+            return Ok(None);
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let target = self.target_input.take().filter(|t| !t.trim().is_empty());
+                self.set_target(target);
+                return Ok(Some(Action::ExitInput));
+            }
+            KeyCode::Esc => {
+                self.target_input = None;
+                return Ok(Some(Action::ExitInput));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         if let Action::PassLayerInfo(li) = action {
-            self.xmin = li.extent.xmin;
-            self.xmax = li.extent.xmax;
-            self.ymin = li.extent.ymin;
-            self.ymax = li.extent.ymax;
+            self.is_loading = false;
+            self.set_extent(li.extent.as_ref(), &li.srs);
+            self.geom_fields = li.geom_fields;
+            self.geom_field = 0;
+        } else if let Action::PassRasterInfo(ri) = action {
+            self.is_loading = false;
+            self.set_extent(Some(&ri.extent), &ri.srs);
+            self.geom_fields = vec![];
+            self.geom_field = 0;
+        } else if let Action::LoadLayer(..) = action {
+            self.is_loading = true;
+        } else if let Action::SelectGeomField(i) = action {
+            if let Some(g) = self.geom_fields.get(i).cloned() {
+                self.set_extent(g.extent.as_ref(), &g.srs);
+                self.geom_field = i;
+            }
+        } else if let Action::NextGeomField = action {
+            if self.is_focused && self.geom_fields.len() > 1 {
+                let next = (self.geom_field + 1) % self.geom_fields.len();
+                return Ok(Some(Action::SelectGeomField(next)));
+            }
+        } else if let Action::NextExtentCrs = action {
+            if self.is_focused {
+                let next = match &self.target {
+                    None => Some(0),
+                    Some(t) => match TARGET_CRS.iter().position(|c| c == t) {
+                        Some(i) if i + 1 < TARGET_CRS.len() => Some(i + 1),
+                        Some(_) => None,
+                        None => Some(0),
+                    },
+                };
+                self.set_target(next.map(|i| TARGET_CRS[i].to_string()));
+            }
+        } else if let Action::OpenCrsSearch = action {
+            if self.is_focused {
+                return Ok(Some(Action::SearchCrs(CrsTarget::Extent)));
+            }
+        } else if let Action::CrsChosen(CrsTarget::Extent, Some(crs)) = action {
+            self.set_target(Some(crs));
+        } else if let Action::TypeExtentCrs = action {
+            if self.is_focused {
+                self.target_input = Some(self.target.clone().unwrap_or_default());
+                return Ok(Some(Action::EnterInput));
+            }
         } /* else if let Action::EnterExtent = action {
             self.focus = true;
         } */;
@@ -60,19 +214,34 @@ impl Component for Extent {
         let rect = inner_rects[1];
 
         let mut block = Block::default()
-            .title(block::Title::from("Extent").alignment(Alignment::Right))
+            .title(block::Title::from(self.title()).alignment(Alignment::Right))
             .borders(Borders::ALL);
 
-        if self.is_focused {
+        if self.is_focused || self.target_input.is_some() {
             block = block.border_set(symbols::border::DOUBLE);
         }
 
-        let xmin_line = Line::from(format!("xmin: {}", self.xmin));
-        let xmax_line = Line::from(format!("xmax: {}", self.xmax));
-        let ymin_line = Line::from(format!("ymin: {}", self.ymin));
-        let ymax_line = Line::from(format!("ymax: {}", self.ymax));
-
-        let extent = Paragraph::new(vec![xmin_line, xmax_line, ymin_line, ymax_line]).block(block);
+        let extent = match &self.extent {
+            Some(extent) => Paragraph::new(extent_lines(extent)),
+            None => Paragraph::new("No extent"),
+        };
+        if let Some(reprojected) = &self.reprojected {
+            // Native extent on the left, reprojected one on the right
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
+                .split(block.inner(rect));
+            let target = match reprojected {
+                Ok((_, extent)) => Paragraph::new(extent_lines(extent)),
+                Err(e) => Paragraph::new(Line::from(e.as_str()).fg(Color::LightRed))
+                    .wrap(Wrap { trim: true }),
+            };
+            f.render_widget(block, rect);
+            f.render_widget(extent, columns[0]);
+            f.render_widget(target, columns[1]);
+        } else {
+            f.render_widget(extent.block(block), rect);
+        }
         /*   let l = List::new(self.items.clone())
         .block(block)
         .highlight_symbol(">> ")
@@ -84,7 +253,6 @@ impl Component for Extent {
 
         //f.render_widget(scrollview, rect);
 
-        f.render_widget(extent, rect);
         Ok(())
     }
 }
@@ -0,0 +1,132 @@
+use color_eyre::eyre::Result;
+use focusable::Focus;
+use ratatui::{prelude::*, widgets::*};
+
+use super::{Component, FocusableWidget};
+use crate::{
+    action::Action,
+    data::features::{FeatureInfo, FieldState},
+    mode::Mode,
+    tui::Frame,
+};
+
+/// Attributes and geometries of a single feature, drawn over the middle column while open.
+#[derive(Debug, Default, Clone)]
+pub struct FeatureDetail {
+    pub featureinfo: Option<FeatureInfo>,
+    pub scroll: u16,
+    pub is_focused: bool,
+}
+
+impl FeatureDetail {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn lines(featureinfo: &FeatureInfo) -> Vec<Line<'_>> {
+        let title = |t: String| Line::from(t).bold().fg(Color::LightYellow);
+        let mut lines = vec![title("Fields".into())];
+        for field in &featureinfo.fields {
+            let value = match &field.value {
+                FieldState::Value(v) => Span::from(v.as_str()),
+                FieldState::Null => Span::from("NULL").italic().fg(Color::DarkGray),
+                FieldState::Unset => Span::from("unset").italic().fg(Color::DarkGray),
+            };
+            lines.push(Line::from(vec![
+                Span::from(field.name.as_str()).fg(Color::LightCyan),
+                Span::from(format!(" ({}): ", field.field_type)).fg(Color::DarkGray),
+                value,
+            ]));
+        }
+        for geometry in &featureinfo.geometries {
+            lines.push(Line::default());
+            lines.push(title(format!("Geometry {}", geometry.name)));
+            lines.push(Line::from(format!(
+                "type: {}, dimensions: {}, vertices: {}",
+                geometry.geometry_type, geometry.dimensions, geometry.vertex_count
+            )));
+            lines.push(Line::from("WKT:").fg(Color::LightCyan));
+            lines.push(Line::from(geometry.wkt.as_str()));
+            lines.push(Line::from("GeoJSON:").fg(Color::LightCyan));
+            lines.push(Line::from(geometry.geojson.as_str()));
+        }
+        lines
+    }
+}
+
+// Focusable only while open
+impl Focus for FeatureDetail {
+    fn can_focus(&self) -> bool {
+        self.featureinfo.is_some()
+    }
+
+    fn is_focused(&self) -> bool {
+        self.is_focused
+    }
+
+    fn focus(&mut self) {
+        self.is_focused = true;
+    }
+
+    fn blur(&mut self) {
+        self.is_focused = false;
+    }
+}
+
+impl FocusableWidget for FeatureDetail {
+    fn mode(&self) -> Mode {
+        Mode::FeatureDetail
+    }
+}
+
+impl Component for FeatureDetail {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::PassFeatureInfo(fi) => {
+                self.featureinfo = Some(fi);
+                self.scroll = 0;
+            }
+            Action::CloseFeature | Action::SelectLayer(..) | Action::PassRasterInfo(_) => {
+                self.featureinfo = None;
+            }
+            Action::ScrollDown if self.is_focused => self.scroll = self.scroll.saturating_add(1),
+            Action::ScrollUp if self.is_focused => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let Some(featureinfo) = &self.featureinfo else {
+            return Ok(());
+        };
+
+        let rects = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, 4), Constraint::Ratio(1, 4)])
+            .split(rect);
+
+        let rect = rects[1];
+
+        let fid = featureinfo
+            .fid
+            .map(|fid| fid.to_string())
+            .unwrap_or_default();
+        let mut block = Block::default()
+            .title(block::Title::from(format!("Feature {fid}")).alignment(Alignment::Right))
+            .borders(Borders::ALL);
+
+        if self.is_focused {
+            block = block.border_set(symbols::border::DOUBLE);
+        }
+
+        let paragraph = Paragraph::new(Self::lines(featureinfo))
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+            .block(block);
+
+        f.render_widget(Clear, rect);
+        f.render_widget(paragraph, rect);
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use focusable::Focus;
+use ratatui::{prelude::*, widgets::*};
+
+use super::{Component, FocusableWidget};
+use crate::{
+    action::Action,
+    data::diff::{
+        parse_tolerance, FeatureChange, FeatureChangeKind, FeatureDiff, FeatureKey,
+        DEFAULT_TOLERANCE,
+    },
+    mode::Mode,
+    tui::Frame,
+};
+
+/// Color of a change, in this list and on the position map.
+pub fn change_color(kind: FeatureChangeKind) -> Color {
+    match kind {
+        FeatureChangeKind::Added => Color::LightGreen,
+        FeatureChangeKind::Removed => Color::LightRed,
+        FeatureChangeKind::Modified => Color::LightYellow,
+    }
+}
+
+/// Input of the key prompt being edited.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum PromptField {
+    #[default]
+    Key,
+    Tolerance,
+}
+
+/// Features added, removed or modified between the selected layer and its version in the next
+/// dataset, drawn over the attribute table while open.
+#[derive(Debug, Default, Clone)]
+pub struct FeatureDiffView {
+    /// Dataset and layer index of the two versions compared
+    layers: Option<((usize, usize), (usize, usize))>,
+    /// Key field being typed, FID if left empty
+    key_input: Option<String>,
+    /// Largest vertex move ignored, kept from one comparison to the next
+    tolerance_input: String,
+    field: PromptField,
+    /// Why the typed tolerance was refused
+    input_error: Option<String>,
+    diff: Option<FeatureDiff>,
+    state: ListState,
+    message: Option<String>,
+    pub is_focused: bool,
+    pub is_loading: bool,
+}
+
+impl FeatureDiffView {
+    pub fn new() -> Self {
+        Self {
+            tolerance_input: format!("{DEFAULT_TOLERANCE:e}"),
+            ..Default::default()
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.layers.is_some() || self.message.is_some()
+    }
+
+    fn title(&self) -> String {
+        let mut title = String::from("Feature diff");
+        if let Some(diff) = &self.diff {
+            let key = match &diff.key {
+                FeatureKey::Fid => "FID",
+                FeatureKey::Field(name) => name.as_str(),
+            };
+            title.push_str(&format!(
+                " by {key}: {} changed, {} unchanged",
+                diff.changes.len(),
+                diff.unchanged
+            ));
+            if !diff.duplicate_keys.is_empty() {
+                title.push_str(&format!(", {} repeated keys", diff.duplicate_keys.len()));
+            }
+            let null_keys = diff.null_keys.before + diff.null_keys.after;
+            if null_keys > 0 {
+                title.push_str(&format!(", {null_keys} without key left out"));
+            }
+        } else if self.is_loading {
+            title.push_str(" (comparing…)");
+        }
+        title
+    }
+
+    fn item(change: &FeatureChange) -> ListItem<'_> {
+        let (symbol, mut details) = match change.kind {
+            FeatureChangeKind::Added => ("+", String::new()),
+            FeatureChangeKind::Removed => ("-", String::new()),
+            FeatureChangeKind::Modified => {
+                let mut names: Vec<&str> =
+                    change.attributes.iter().map(|a| a.name.as_str()).collect();
+                if change.geometry_changed {
+                    names.push("geometry");
+                }
+                ("~", format!(": {}", names.join(", ")))
+            }
+        };
+        if let [a] = change.attributes.as_slice() {
+            let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "NULL".into());
+            details.push_str(&format!(
+                " ({} → {})",
+                value(&a.value.before),
+                value(&a.value.after)
+            ));
+        }
+        ListItem::new(format!("{symbol} {}{details}", change.key)).fg(change_color(change.kind))
+    }
+}
+
+// Focusable while shown, once the key is typed
+impl Focus for FeatureDiffView {
+    fn can_focus(&self) -> bool {
+        self.is_open() && self.key_input.is_none()
+    }
+
+    fn is_focused(&self) -> bool {
+        self.is_focused
+    }
+
+    fn focus(&mut self) {
+        self.is_focused = true;
+    }
+
+    fn blur(&mut self) {
+        self.is_focused = false;
+    }
+}
+
+impl FocusableWidget for FeatureDiffView {
+    fn mode(&self) -> Mode {
+        Mode::FeatureDiff
+    }
+}
+
+impl Component for FeatureDiffView {
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let Some(input) = &mut self.key_input else {
+            return Ok(None);
+        };
+        let input = match self.field {
+            PromptField::Key => input,
+            PromptField::Tolerance => &mut self.tolerance_input,
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Tab => {
+                self.field = match self.field {
+                    PromptField::Key => PromptField::Tolerance,
+                    PromptField::Tolerance => PromptField::Key,
+                }
+            }
+            KeyCode::Enter => {
+                let tolerance = match parse_tolerance(&self.tolerance_input) {
+                    Ok(tolerance) => tolerance,
+                    Err(e) => {
+                        self.input_error = Some(e);
+                        return Ok(None);
+                    }
+                };
+                let key = match self.key_input.take().unwrap_or_default().trim() {
+                    "" => FeatureKey::Fid,
+                    name => FeatureKey::Field(name.to_string()),
+                };
+                if let Some((before, after)) = self.layers {
+                    self.is_loading = true;
+                    return Ok(Some(Action::DiffFeatures(before, after, key, tolerance)));
+                }
+            }
+            KeyCode::Esc => {
+                self.key_input = None;
+                self.layers = None;
+                return Ok(Some(Action::ExitInput));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::OpenFeatureDiff(before, after) => {
+                self.layers = Some((before, after));
+                self.key_input = Some(String::new());
+                self.field = PromptField::Key;
+                self.input_error = None;
+                self.diff = None;
+                self.message = None;
+                return Ok(Some(Action::EnterInput));
+            }
+            Action::DiffFeatures(..) => return Ok(Some(Action::ExitInput)),
+            Action::PassFeatureDiff(diff) => {
+                self.is_loading = false;
+                self.diff = Some(diff);
+                self.state.select(None);
+            }
+            Action::FeatureDiffFailed(e) => {
+                self.is_loading = false;
+                self.message = Some(e);
+            }
+            Action::CloseFeatureDiff => {
+                self.is_loading = false;
+                self.layers = None;
+                self.diff = None;
+                self.message = None;
+            }
+            Action::NextRow if self.is_focused => {
+                let len = self.diff.as_ref().map_or(0, |d| d.changes.len());
+                if len > 0 {
+                    self.state.select(Some(
+                        self.state.selected().map_or(0, |i| (i + 1).min(len - 1)),
+                    ));
+                }
+            }
+            Action::PreviousRow if self.is_focused => {
+                self.state
+                    .select(self.state.selected().map(|i| i.saturating_sub(1)));
+            }
+            Action::ShowFeature if self.is_focused => {
+                let change = self
+                    .state
+                    .selected()
+                    .zip(self.diff.as_ref())
+                    .and_then(|(i, d)| d.changes.get(i));
+                if let (Some(change), Some((before, after))) = (change, self.layers) {
+                    let (d, l) = match change.kind {
+                        FeatureChangeKind::Removed => before,
+                        _ => after,
+                    };
+                    if let Some(fid) = change.fid {
+                        return Ok(Some(Action::ReadFeature(d, l, fid)));
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        if !self.is_open() {
+            return Ok(());
+        }
+
+        let rects = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
+            .split(rect);
+
+        let inner_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
+            .split(rects[1]);
+
+        let rect = inner_rects[1];
+
+        let mut block = Block::default()
+            .title(block::Title::from(self.title()).alignment(Alignment::Left))
+            .borders(Borders::ALL);
+
+        if self.is_focused || self.key_input.is_some() {
+            block = block.border_set(symbols::border::DOUBLE);
+        }
+
+        f.render_widget(Clear, rect);
+        if let Some(input) = &self.key_input {
+            let line = |label: &str, value: &str, field: PromptField| {
+                if self.field == field {
+                    Line::from(format!("{label}: {value}▏")).fg(Color::LightYellow)
+                } else {
+                    Line::from(format!("{label}: {value}"))
+                }
+            };
+            let mut lines = vec![
+                line("Key field", input, PromptField::Key),
+                Line::from("Leave empty to match features by FID").fg(Color::DarkGray),
+                line("Tolerance", &self.tolerance_input, PromptField::Tolerance),
+                Line::from("Largest vertex move ignored, in layer units").fg(Color::DarkGray),
+                Line::default(),
+                Line::from("<tab> next input  <enter> compare  <esc> cancel").fg(Color::DarkGray),
+            ];
+            if let Some(e) = &self.input_error {
+                lines.push(Line::from(e.as_str()).fg(Color::LightRed));
+            }
+            let prompt = Paragraph::new(lines).block(block);
+            f.render_widget(prompt, rect);
+        } else if let Some(message) = &self.message {
+            let paragraph = Paragraph::new(Line::from(message.as_str()).fg(Color::LightRed))
+                .wrap(Wrap { trim: true })
+                .block(block);
+            f.render_widget(paragraph, rect);
+        } else {
+            let items: Vec<ListItem> = self
+                .diff
+                .iter()
+                .flat_map(|d| d.changes.iter().map(Self::item))
+                .collect();
+            let l = List::new(items)
+                .block(block)
+                .highlight_symbol(">> ")
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            f.render_stateful_widget(l, rect, &mut self.state);
+        }
+        Ok(())
+    }
+}
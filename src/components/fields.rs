@@ -8,49 +8,154 @@ use ratatui::{prelude::*, widgets::*};
 use tui_scrollview::{self, ScrollView, ScrollViewState};
 
 use super::{Component, FocusableWidget};
-use crate::{action::Action, data::LayerInfo, tui::Frame};
+use crate::{
+    action::Action,
+    data::{
+        fields::{FieldDefinition, FieldStats, GeomFieldDefinition, MAX_DISTINCT},
+        LayerInfo,
+    },
+    mode::Mode,
+    tui::Frame,
+};
 
 #[derive(Debug, Default, Clone, Focus)]
 pub struct Fields {
-    pub geom_field: Vec<String>,
-    pub fields: Vec<(String, u32)>,
+    pub geom_fields: Vec<GeomFieldDefinition>,
+    pub fields: Vec<FieldDefinition>,
+    pub stats: Vec<FieldStats>,
+    pub state: TableState,
     pub is_focused: bool,
+    pub is_loading: bool,
+    /// Hidden while a raster is selected, the `Raster` panel takes its place
+    pub is_hidden: bool,
 }
 
 impl Fields {
     pub fn from_layerinfo(li: &LayerInfo) -> Self {
         Self {
-            geom_field: li.fields.geom_field.clone(),
+            geom_fields: li.fields.geom_fields.clone(),
             fields: li.fields.fields.clone(),
+            stats: li.fields.stats.clone(),
+            state: Default::default(),
             is_focused: false,
+            is_loading: false,
+            is_hidden: false,
         }
     }
 }
 
-impl FocusableWidget for Fields {}
+impl Fields {
+    fn field_row(field: &FieldDefinition) -> Row<'_> {
+        let flag = |b: bool| if b { "✓" } else { "" };
+        let type_name = match &field.subtype {
+            Some(subtype) => format!("{}({subtype})", field.type_name),
+            None => field.type_name.clone(),
+        };
+        let width = match (field.width, field.precision) {
+            (0, _) => String::new(),
+            (w, 0) => w.to_string(),
+            (w, p) => format!("{w}.{p}"),
+        };
+        [
+            field.name.clone(),
+            type_name,
+            width,
+            flag(field.nullable).into(),
+            flag(field.unique).into(),
+            field.default.clone().unwrap_or_default(),
+            field.alternative_name.clone().unwrap_or_default(),
+            field.comment.clone().unwrap_or_default(),
+            field.domain.clone().unwrap_or_default(),
+        ]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+    }
+
+    fn geom_field_line(field: &GeomFieldDefinition) -> Line<'_> {
+        let srs = field.srs.as_deref().unwrap_or("no CRS");
+        let nullable = if field.nullable { ", nullable" } else { "" };
+        Line::from(vec![
+            Span::from(field.name.as_str()).fg(Color::LightCyan),
+            Span::from(format!(" {}, {srs}{nullable}", field.geometry_type)),
+        ])
+    }
+
+    fn stats_lines(stats: &FieldStats) -> Vec<Line<'_>> {
+        let distinct = stats
+            .distinct
+            .map_or(format!("> {MAX_DISTINCT}"), |d| d.to_string());
+        let mut lines = vec![Line::from(format!(
+            "nulls: {}, distinct: {distinct}",
+            stats.null
+        ))];
+        if let (Some(min), Some(max)) = (&stats.min, &stats.max) {
+            let mean = stats
+                .mean
+                .map(|m| format!(", mean: {m:.3}"))
+                .unwrap_or_default();
+            lines.push(Line::from(format!("min: {min}, max: {max}{mean}")));
+        }
+        if let Some((min, max)) = stats.length {
+            lines.push(Line::from(format!("length: {min} to {max}")));
+        }
+        lines
+    }
+}
+
+impl FocusableWidget for Fields {
+    fn mode(&self) -> Mode {
+        Mode::Fields
+    }
+}
 
 impl Component for Fields {
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         if let Action::PassLayerInfo(li) = action {
-            self.geom_field = li.fields.geom_field;
+            self.is_loading = false;
+            self.geom_fields = li.fields.geom_fields;
             self.fields = li.fields.fields;
+            self.stats = li.fields.stats;
+            self.state.select(None);
+            self.is_hidden = false;
+        } else if let Action::PassRasterInfo(_) = action {
+            self.is_loading = false;
+            self.is_hidden = true;
+        } else if let Action::LoadLayer(..) = action {
+            self.is_loading = true;
+            self.is_hidden = false;
+        } else if !self.is_focused || self.fields.is_empty() {
+            // Selecting a field is for the focused panel only
+        } else if let Action::NextRow = action {
+            let last = self.fields.len() - 1;
+            self.state
+                .select(Some(self.state.selected().map_or(0, |i| (i + 1).min(last))));
+        } else if let Action::PreviousRow = action {
+            self.state
+                .select(self.state.selected().map(|i| i.saturating_sub(1)));
         };
         Ok(None)
     }
 
     fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        if self.is_hidden {
+            return Ok(());
+        }
+
         let header_style = Style::default();
 
         let selected_style = Style::default()
             .add_modifier(Modifier::REVERSED)
             .fg(Color::LightYellow);
 
-        let header = ["Name", "Type"]
-            .into_iter()
-            .map(Cell::from)
-            .collect::<Row>()
-            .style(header_style)
-            .height(1);
+        let header = [
+            "Name", "Type", "Width", "Null", "Unique", "Default", "Alias", "Comment", "Domain",
+        ]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(header_style)
+        .height(1);
 
         let rects = Layout::default()
             .direction(Direction::Horizontal)
@@ -69,47 +174,66 @@ impl Component for Fields {
         let rect = inner_rects[2];
 
         let mut block = Block::default()
-            .title(block::Title::from("Fields").alignment(Alignment::Right))
+            .title(
+                block::Title::from(if self.is_loading {
+                    "Fields (loading…)"
+                } else {
+                    "Fields"
+                })
+                .alignment(Alignment::Right),
+            )
             .borders(Borders::ALL);
 
         if self.is_focused {
             block = block.border_set(symbols::border::DOUBLE);
         }
 
-        let rows = self.fields.iter().enumerate().map(|(i, data)| {
+        let stats = self.state.selected().and_then(|i| self.stats.get(i));
+        let panel_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Min(0),
+                Constraint::Length(if stats.is_some() { 5 } else { 0 }),
+            ])
+            .split(rect);
+
+        let geom_lines: Vec<Line> = self.geom_fields.iter().map(Self::geom_field_line).collect();
+        let table_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(geom_lines.len() as u16),
+                Constraint::Min(0),
+            ])
+            .split(block.inner(panel_rects[0]));
+
+        let rows = self.fields.iter().enumerate().map(|(i, field)| {
             let color = match i % 2 {
                 0 => Color::Black,
-                _ => Color::Gray,
+                _ => Color::DarkGray,
             };
-            let item = [&data.0, &data.1.to_string()];
-            item.into_iter()
-                .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
-                .collect::<Row>()
-                .style(Style::new().fg(Color::LightCyan).bg(color))
-                .height(2)
+            Self::field_row(field).style(Style::new().fg(Color::LightCyan).bg(color))
         });
 
         let bar = " █ ";
         let t = Table::new(
             rows,
             [
-                // + 1 is for padding.
-                Constraint::Length(10),
                 Constraint::Min(10),
                 Constraint::Min(10),
+                Constraint::Length(5),
+                Constraint::Length(4),
+                Constraint::Length(6),
+                Constraint::Min(7),
+                Constraint::Min(5),
+                Constraint::Min(7),
+                Constraint::Min(6),
             ],
         )
         .header(header)
         .highlight_style(selected_style)
-        .highlight_symbol(Text::from(vec![
-            "".into(),
-            bar.into(),
-            bar.into(),
-            "".into(),
-        ]))
+        .highlight_symbol(bar)
         .bg(Color::Black)
-        .highlight_spacing(HighlightSpacing::Always)
-        .block(block);
+        .highlight_spacing(HighlightSpacing::Always);
 
         /*   let l = List::new(self.items.clone())
         .block(block)
@@ -122,7 +246,14 @@ impl Component for Fields {
 
         //f.render_widget(scrollview, rect);
 
-        f.render_widget(t, rect);
+        f.render_widget(block, panel_rects[0]);
+        f.render_widget(Paragraph::new(geom_lines), table_rects[0]);
+        f.render_stateful_widget(t, table_rects[1], &mut self.state);
+        if let Some(stats) = stats {
+            let stats_view = Paragraph::new(Self::stats_lines(stats))
+                .block(Block::default().title("Statistics").borders(Borders::ALL));
+            f.render_widget(stats_view, panel_rects[1]);
+        }
         Ok(())
     }
 }
@@ -0,0 +1,251 @@
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use focusable::Focus;
+use ratatui::{prelude::*, widgets::*};
+
+use super::{Component, FocusableWidget};
+use crate::{
+    action::Action,
+    data::{
+        geometries::{GeometryProblem, GeometrySummary, MAX_PROBLEMS},
+        LayerInfo,
+    },
+    mode::Mode,
+    tui::Frame,
+};
+
+/// Geometry types of the selected layer and the features with a null, empty, unconvertible or
+/// invalid geometry.
+#[derive(Debug, Default, Clone, Focus)]
+pub struct Geometries {
+    /// Dataset and layer index of the selected layer
+    layer: Option<(usize, usize)>,
+    summary: GeometrySummary,
+    /// Name of the selected layer, for the file of the exported problems
+    name: String,
+    state: ListState,
+    /// Path of the GeoJSON file being typed by the user
+    export_input: Option<String>,
+    message: Option<String>,
+    pub is_focused: bool,
+    pub is_loading: bool,
+}
+
+impl Geometries {
+    pub fn from_layerinfo(li: &LayerInfo) -> Self {
+        Self {
+            summary: li.geometry_summary.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn title(&self) -> String {
+        let mut title = String::from("Geometries");
+        if self.is_loading {
+            title.push_str(" (loading…)");
+        }
+        if let Some(input) = &self.export_input {
+            title.push_str(&format!(" GeoJSON file: {input}▏"));
+        } else if let Some(message) = &self.message {
+            title.push_str(&format!(" {message}"));
+        }
+        title
+    }
+
+    /// Where the selected problem lies, if it has a location.
+    fn locate(&self) -> Action {
+        let position = self
+            .state
+            .selected()
+            .and_then(|i| self.summary.problems.get(i))
+            .and_then(|p| match &p.problem {
+                GeometryProblem::Invalid(i) => Some((i.x, i.y)),
+                _ => None,
+            });
+        Action::LocateProblem(position)
+    }
+
+    fn lines(&self) -> Vec<Line<'_>> {
+        let s = &self.summary;
+        let mut lines: Vec<Line> = s
+            .types
+            .iter()
+            .map(|(t, n)| Line::from(format!("{t}: {n}")))
+            .collect();
+        let problems = [
+            ("null", s.null),
+            ("empty", s.empty),
+            ("unconvertible", s.unconvertible),
+            ("invalid", s.invalid),
+        ]
+        .map(|(name, n)| {
+            let span = Span::from(format!("{name}: {n}"));
+            if n > 0 {
+                span.fg(Color::LightRed)
+            } else {
+                span
+            }
+        });
+        lines.push(Line::from(problems.to_vec()));
+        lines
+    }
+}
+
+impl FocusableWidget for Geometries {
+    fn mode(&self) -> Mode {
+        Mode::Geometries
+    }
+}
+
+impl Component for Geometries {
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let Some(input) = &mut self.export_input else {
+            return Ok(None);
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let path = self.export_input.take().unwrap_or_default();
+                let Some((d, l)) = self.layer else {
+                    return Ok(Some(Action::ExitInput));
+                };
+                self.message = Some("writing…".into());
+                let summary = self.summary.clone();
+                return Ok(Some(Action::WriteProblems(d, l, summary, path.into())));
+            }
+            KeyCode::Esc => {
+                self.export_input = None;
+                return Ok(Some(Action::ExitInput));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::SelectLayer(d, l) => self.layer = Some((d, l)),
+            Action::LoadLayer(..) => self.is_loading = true,
+            Action::PassLayerInfo(li) => {
+                self.is_loading = false;
+                self.summary = li.geometry_summary;
+                self.name = li.name;
+                self.message = None;
+                self.state.select(None);
+            }
+            Action::PassRasterInfo(_) => {
+                self.is_loading = false;
+                self.layer = None;
+                self.summary = GeometrySummary::default();
+                self.message = None;
+                self.state.select(None);
+            }
+            Action::NextRow if self.is_focused && !self.summary.problems.is_empty() => {
+                let last = self.summary.problems.len() - 1;
+                self.state
+                    .select(Some(self.state.selected().map_or(0, |i| (i + 1).min(last))));
+                return Ok(Some(self.locate()));
+            }
+            Action::PreviousRow if self.is_focused => {
+                self.state
+                    .select(self.state.selected().map(|i| i.saturating_sub(1)));
+                return Ok(Some(self.locate()));
+            }
+            Action::WriteProblems(..) => return Ok(Some(Action::ExitInput)),
+            Action::ProblemsWritten(path, n) => {
+                let path = path.display();
+                let located = self.summary.invalid;
+                self.message = Some(if (n as u64) < located {
+                    format!(
+                        "{n} of {located} invalid geometries written to {path}, \
+                        the first {MAX_PROBLEMS} problems only being kept"
+                    )
+                } else {
+                    format!("{n} problems written to {path}")
+                });
+            }
+            Action::WriteProblemsFailed(e) => self.message = Some(format!("Export failed: {e}")),
+            Action::ExportProblems if self.is_focused && self.layer.is_some() => {
+                self.message = None;
+                self.export_input = Some(format!("{}_problems.geojson", self.name));
+                return Ok(Some(Action::EnterInput));
+            }
+            Action::ShowFeature if self.is_focused => {
+                let fid = self
+                    .state
+                    .selected()
+                    .and_then(|i| self.summary.problems.get(i))
+                    .and_then(|p| p.fid);
+                if let (Some((d, l)), Some(fid)) = (self.layer, fid) {
+                    return Ok(Some(Action::ReadFeature(d, l, fid)));
+                }
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let rects = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, 4)])
+            .split(rect);
+
+        let inner_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(60), Constraint::Fill(1)])
+            .split(rects[0]);
+
+        let rect = inner_rects[1];
+
+        let mut block = Block::default()
+            .title(block::Title::from(self.title()).alignment(Alignment::Left))
+            .borders(Borders::ALL);
+
+        if self.is_focused || self.export_input.is_some() {
+            block = block.border_set(symbols::border::DOUBLE);
+        }
+
+        let lines = self.lines();
+        let panel_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(lines.len() as u16),
+                Constraint::Min(0),
+            ])
+            .split(block.inner(rect));
+
+        let mut items: Vec<ListItem> = self
+            .summary
+            .problems
+            .iter()
+            .map(|p| {
+                let fid = p.fid.map(|fid| fid.to_string()).unwrap_or_default();
+                ListItem::new(Line::from(vec![
+                    Span::from(format!("FID {fid}: ")).fg(Color::LightCyan),
+                    Span::from(p.problem.to_string()),
+                ]))
+            })
+            .collect();
+        let listed = self.summary.problems.len() as u64;
+        if self.summary.problem_count() > listed {
+            let more = self.summary.problem_count() - listed;
+            items.push(ListItem::new(format!("… {more} more")).italic());
+        }
+
+        let l = List::new(items)
+            .highlight_symbol(">> ")
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        f.render_widget(block, rect);
+        f.render_widget(
+            Paragraph::new(lines).wrap(Wrap { trim: true }),
+            panel_rects[0],
+        );
+        f.render_stateful_widget(l, panel_rects[1], &mut self.state);
+        Ok(())
+    }
+}
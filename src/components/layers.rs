@@ -1,52 +1,140 @@
 use std::{borrow::BorrowMut, time::Instant};
 
 use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
 use gdal::{
     vector::{Layer, LayerAccess},
     Dataset, LayerIterator,
 };
 use ratatui::{prelude::*, widgets::*};
+use tokio::sync::mpsc::UnboundedSender;
 
 use super::{Component, Focus, FocusableWidget};
-use crate::{action::Action, data::LayerInfo, tui::Frame};
+use crate::{
+    action::Action,
+    data::{DatasetInfo, LayerFilter, LayerInfo},
+    mode::Mode,
+    tui::Frame,
+};
+
+/// A visible row of the dataset tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TreeItem {
+    Dataset(usize),
+    Raster(usize),
+    Layer(usize, usize),
+}
 
 #[derive(Debug, Focus, Clone)]
 pub struct LayerList {
-    pub layerinfos: Vec<LayerInfo>,
+    command_tx: Option<UnboundedSender<Action>>,
+    pub datasetinfos: Vec<DatasetInfo>,
+    /// Layers loaded so far by the GDAL thread, per dataset
+    pub layerinfos: Vec<Vec<Option<LayerInfo>>>,
+    /// Filters applied to each layer
+    filters: Vec<Vec<LayerFilter>>,
+    /// Attribute filter being typed by the user
+    filter_input: Option<String>,
+    /// Why GDAL rejected the last attribute filter
+    filter_error: Option<String>,
+    expanded: Vec<bool>,
     is_focused: bool,
     state: ListState,
 }
 
 impl LayerList {
-    pub fn new(dataset: Dataset) -> Self {
-        let layerinfos = LayerInfo::from_dataset(&dataset);
-        let state = ListState::default().with_selected(Some(0));
-        Self {
+    pub fn new(datasets: &[Dataset]) -> Self {
+        let datasetinfos: Vec<DatasetInfo> = datasets.iter().map(DatasetInfo::from).collect();
+        let expanded = vec![true; datasetinfos.len()];
+        let layerinfos: Vec<Vec<Option<LayerInfo>>> = datasetinfos
+            .iter()
+            .map(|d| vec![None; d.layer_names.len()])
+            .collect();
+        let filters = layerinfos
+            .iter()
+            .map(|l| vec![LayerFilter::default(); l.len()])
+            .collect();
+        let mut layer_list = Self {
+            command_tx: None,
+            datasetinfos,
             layerinfos,
+            filters,
+            filter_input: None,
+            filter_error: None,
+            expanded,
             is_focused: true,
-            state,
+            state: ListState::default(),
+        };
+        // Start on the first leaf rather than on a dataset node
+        let first_layer = layer_list
+            .items()
+            .iter()
+            .position(|i| !matches!(i, TreeItem::Dataset(_)));
+        layer_list.state.select(Some(first_layer.unwrap_or(0)));
+        layer_list
+    }
+
+    fn items(&self) -> Vec<TreeItem> {
+        let mut items = vec![];
+        for (d, datasetinfo) in self.datasetinfos.iter().enumerate() {
+            items.push(TreeItem::Dataset(d));
+            if self.expanded[d] {
+                if datasetinfo.rasterinfo.is_some() {
+                    items.push(TreeItem::Raster(d));
+                }
+                items.extend((0..datasetinfo.layer_names.len()).map(|l| TreeItem::Layer(d, l)));
+            }
         }
+        items
     }
 
-    fn names(&self) -> Vec<String> {
-        let names: Vec<String> = self.layerinfos.iter().map(|l| l.name.clone()).collect();
-        names
+    fn selected_item(&self) -> Option<TreeItem> {
+        self.items().get(self.state.selected()?).copied()
     }
 
-    fn layer_idx(&self) -> usize {
-        self.state.selected().unwrap_or(0)
+    /// The `LayerInfo` of the selected leaf, if a layer is selected and loaded.
+    pub fn layerinfo(&self) -> Option<&LayerInfo> {
+        match self.selected_item()? {
+            TreeItem::Layer(d, l) => self.layerinfos[d][l].as_ref(),
+            TreeItem::Dataset(_) | TreeItem::Raster(_) => None,
+        }
+    }
+
+    fn layer_action(&self) -> Option<Action> {
+        match self.selected_item()? {
+            TreeItem::Raster(d) => self.datasetinfos[d]
+                .rasterinfo
+                .clone()
+                .map(Action::PassRasterInfo),
+            TreeItem::Layer(d, l) => match &self.layerinfos[d][l] {
+                Some(layerinfo) => Some(Action::PassLayerInfo(layerinfo.clone())),
+                None => Some(Action::LoadLayer(d, l)),
+            },
+            TreeItem::Dataset(_) => None,
+        }
+    }
+
+    /// Announce the newly selected leaf, and return what the panels need to display it.
+    fn on_selection(&self) -> Result<Option<Action>> {
+        if let (Some(tx), Some(TreeItem::Layer(d, l))) = (&self.command_tx, self.selected_item()) {
+            tx.send(Action::SelectLayer(d, l))?;
+        }
+        Ok(self.layer_action())
     }
 
-    /*     fn layer(&self) -> Layer {
-        self.dataset
-            .layer(self.state.selected().unwrap_or(0) as isize)
-            .unwrap()
-    } */
+    /// Filters of the selected layer.
+    fn selected_filter(&self) -> Option<&LayerFilter> {
+        match self.selected_item()? {
+            TreeItem::Layer(d, l) => Some(&self.filters[d][l]),
+            TreeItem::Dataset(_) | TreeItem::Raster(_) => None,
+        }
+    }
 
     fn next(&mut self) {
+        let len = self.items().len();
         let i = match self.state.selected() {
             Some(i) => {
-                if i >= self.layerinfos.len() - 1 {
+                if i + 1 >= len {
                     0
                 } else {
                     i + 1
@@ -60,10 +148,11 @@ impl LayerList {
     // Select the previous item. This will not be reflected until the widget is drawn in the
     // `Terminal::draw` callback using `Frame::render_stateful_widget`.
     fn previous(&mut self) {
+        let len = self.items().len();
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.layerinfos.len() - 1
+                    len.saturating_sub(1)
                 } else {
                     i - 1
                 }
@@ -72,22 +161,177 @@ impl LayerList {
         };
         self.state.select(Some(i));
     }
+
+    /// Expand or collapse the dataset under the cursor. On a leaf, its parent dataset is
+    /// collapsed and selected.
+    fn toggle(&mut self) {
+        let d = match self.selected_item() {
+            Some(TreeItem::Dataset(d))
+            | Some(TreeItem::Raster(d))
+            | Some(TreeItem::Layer(d, _)) => d,
+            None => return,
+        };
+        self.expanded[d] = !self.expanded[d];
+        let position = self.items().iter().position(|i| *i == TreeItem::Dataset(d));
+        self.state.select(position);
+    }
 }
 
-impl FocusableWidget for LayerList {}
+impl FocusableWidget for LayerList {
+    fn mode(&self) -> Mode {
+        Mode::LayerList
+    }
+}
 
 impl Component for LayerList {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.command_tx = Some(tx);
+        Ok(())
+    }
+
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let Some(input) = &mut self.filter_input else {
+            return Ok(None);
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            // The prompt stays open until GDAL accepts the filter
+            KeyCode::Enter => {
+                let filter = Some(input.trim().to_string()).filter(|f| !f.is_empty());
+                if let (Some(tx), Some(TreeItem::Layer(d, l))) =
+                    (&self.command_tx, self.selected_item())
+                {
+                    tx.send(Action::SetAttributeFilter(d, l, filter))?;
+                }
+            }
+            KeyCode::Esc => {
+                self.filter_input = None;
+                self.filter_error = None;
+                return Ok(Some(Action::ExitInput));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn init(&mut self, area: Rect) -> Result<()> {
+        // Let the other panels know about the initial selection
+        if let (Some(tx), Some(action)) = (&self.command_tx, self.on_selection()?) {
+            tx.send(action)?;
+        }
+        Ok(())
+    }
+
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         if let Action::UpLayer = action {
             self.previous();
-            Ok(Some(Action::PassLayerInfo(
-                self.layerinfos[self.layer_idx()].clone(),
-            )))
+            self.on_selection()
         } else if let Action::DownLayer = action {
             self.next();
-            Ok(Some(Action::PassLayerInfo(
-                self.layerinfos[self.layer_idx()].clone(),
-            )))
+            self.on_selection()
+        } else if let Action::ToggleDataset = action {
+            self.toggle();
+            Ok(None)
+        } else if let Action::EditFilter = action {
+            if !self.is_focused || !matches!(self.selected_item(), Some(TreeItem::Layer(..))) {
+                return Ok(None);
+            }
+            self.filter_input = Some(
+                self.selected_filter()
+                    .and_then(|f| f.attribute.clone())
+                    .unwrap_or_default(),
+            );
+            self.filter_error = None;
+            Ok(Some(Action::EnterInput))
+        } else if let Action::AttributeFilterSet(d, l, filter) = action {
+            self.filters[d][l].attribute = filter;
+            self.layerinfos[d][l] = None;
+            self.filter_error = None;
+            if self.filter_input.take().is_some() {
+                if let Some(tx) = &self.command_tx {
+                    tx.send(Action::ExitInput)?;
+                }
+            }
+            // Reload the layer so that every panel shows the matching features only
+            if self.selected_item() == Some(TreeItem::Layer(d, l)) {
+                self.on_selection()
+            } else {
+                Ok(None)
+            }
+        } else if let Action::SpatialFilterSet(d, l, filter) = action {
+            self.filters[d][l].spatial = filter;
+            self.layerinfos[d][l] = None;
+            if self.selected_item() == Some(TreeItem::Layer(d, l)) {
+                self.on_selection()
+            } else {
+                Ok(None)
+            }
+        } else if let Action::AttributeFilterFailed(_, _, e) = action {
+            self.filter_error = Some(e);
+            Ok(None)
+        } else if let Action::CompareDatasets = action {
+            let d = match self.selected_item() {
+                Some(TreeItem::Dataset(d) | TreeItem::Raster(d) | TreeItem::Layer(d, _)) => d,
+                None => return Ok(None),
+            };
+            let n = self.datasetinfos.len();
+            if !self.is_focused || n < 2 {
+                return Ok(None);
+            }
+            Ok(Some(Action::OpenSchemaDiff(d, (d + 1) % n)))
+        } else if let Action::CompareFeatures = action {
+            let (Some(TreeItem::Layer(d, l)), true) = (self.selected_item(), self.is_focused)
+            else {
+                return Ok(None);
+            };
+            let n = self.datasetinfos.len();
+            if n < 2 {
+                return Ok(None);
+            }
+            let other = (d + 1) % n;
+            let (names, other_names) = (
+                &self.datasetinfos[d].layer_names,
+                &self.datasetinfos[other].layer_names,
+            );
+            // Lone layers are two versions of each other whatever their name
+            let other_layer = match (names.len(), other_names.len()) {
+                (1, 1) => Some(0),
+                _ => other_names.iter().position(|name| *name == names[l]),
+            };
+            Ok(Some(match other_layer {
+                Some(m) => Action::OpenFeatureDiff((d, l), (other, m)),
+                None => Action::FeatureDiffFailed(format!(
+                    "No layer named '{}' in {}",
+                    names[l], self.datasetinfos[other].name
+                )),
+            }))
+        } else if let Action::SqlResult(Some(datasetinfo)) = action {
+            // The result set is a new dataset on the GDAL thread, listed after the others
+            let d = self.datasetinfos.len();
+            let layers = datasetinfo.layer_names.len();
+            self.datasetinfos.push(datasetinfo);
+            self.layerinfos.push(vec![None; layers]);
+            self.filters.push(vec![LayerFilter::default(); layers]);
+            self.expanded.push(true);
+            let position = self
+                .items()
+                .iter()
+                .position(|i| *i == TreeItem::Layer(d, 0));
+            if position.is_none() {
+                return Ok(None);
+            }
+            self.state.select(position);
+            self.on_selection()
+        } else if let Action::LayerLoaded(d, l, layerinfo) = action {
+            self.layerinfos[d][l] = Some(layerinfo);
+            if self.selected_item() == Some(TreeItem::Layer(d, l)) {
+                Ok(self.layer_action())
+            } else {
+                Ok(None)
+            }
         } else {
             Ok(None)
         }
@@ -101,7 +345,12 @@ impl Component for LayerList {
             ])
             .split(rect);
 
-        let rect = rects[0];
+        let inner_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(60), Constraint::Fill(1)])
+            .split(rects[0]);
+
+        let rect = inner_rects[0];
 
         let mut block = Block::default()
             .title(block::Title::from("Layer list").alignment(Alignment::Left))
@@ -111,16 +360,70 @@ impl Component for LayerList {
             block = block.border_set(symbols::border::DOUBLE);
         }
 
-        let l = List::new(self.names())
-            .block(block)
-            .highlight_symbol(">> ")
-            .highlight_style(
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .add_modifier(Modifier::REVERSED),
+        let items: Vec<ListItem> = self
+            .items()
+            .into_iter()
+            .map(|item| match item {
+                TreeItem::Dataset(d) => {
+                    let marker = if self.expanded[d] { "▾" } else { "▸" };
+                    ListItem::new(format!("{marker} {}", self.datasetinfos[d].name)).bold()
+                }
+                TreeItem::Raster(d) => {
+                    let bands = self.datasetinfos[d]
+                        .rasterinfo
+                        .as_ref()
+                        .map_or(0, |r| r.bands.len());
+                    ListItem::new(format!("  raster ({bands} bands)")).italic()
+                }
+                TreeItem::Layer(d, l) => {
+                    let name = &self.datasetinfos[d].layer_names[l];
+                    match self.filters[d][l].is_empty() {
+                        false => ListItem::new(Line::from(vec![
+                            Span::from(format!("  {name} ")),
+                            Span::from("(filtered)").fg(Color::LightYellow),
+                        ])),
+                        true => ListItem::new(format!("  {name}")),
+                    }
+                }
+            })
+            .collect();
+
+        let mut filter_lines = vec![];
+        if let Some(input) = &self.filter_input {
+            filter_lines.push(Line::from(format!("WHERE {input}▏")).fg(Color::LightYellow));
+        } else if let Some(filter) = self.selected_filter().and_then(|f| f.attribute.as_ref()) {
+            filter_lines.push(Line::from(format!("WHERE {filter}")).fg(Color::DarkGray));
+        }
+        if let Some(spatial) = self.selected_filter().and_then(|f| f.spatial.as_ref()) {
+            let e = &spatial.extent;
+            filter_lines.push(
+                Line::from(format!("BBOX {} {} {} {}", e.xmin, e.ymin, e.xmax, e.ymax))
+                    .fg(Color::DarkGray),
             );
+        }
+        if let Some(e) = &self.filter_error {
+            filter_lines.push(Line::from(e.as_str()).fg(Color::LightRed));
+        }
+        let width = block.inner(rect).width.max(1);
+        let filter_height = filter_lines
+            .iter()
+            .map(|l| (l.width() as u16).div_ceil(width).max(1))
+            .sum();
+        let filter = Paragraph::new(filter_lines).wrap(Wrap { trim: false });
+        let panel_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Min(0), Constraint::Length(filter_height)])
+            .split(block.inner(rect));
+
+        let l = List::new(items).highlight_symbol(">> ").highlight_style(
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::REVERSED),
+        );
 
-        f.render_stateful_widget(l, rect, &mut self.state);
+        f.render_widget(block, rect);
+        f.render_stateful_widget(l, panel_rects[0], &mut self.state);
+        f.render_widget(filter, panel_rects[1]);
         Ok(())
     }
 }
@@ -2,55 +2,597 @@ use focusable::Focus;
 use std::default;
 
 use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use gdal::{
-    spatial_ref::SpatialRef,
+    spatial_ref::{CoordTransform, SpatialRef},
     vector::{Layer, LayerAccess},
 };
+use geo::{CoordsIter, EuclideanDistance, LinesIter};
 use layout::Size;
-use ratatui::widgets::canvas::{Canvas, Map, MapResolution, Rectangle};
+use ratatui::widgets::canvas::{self, Canvas, Context, Map, MapResolution, Points, Rectangle};
 use ratatui::{prelude::*, widgets::*};
+use tokio::sync::mpsc::UnboundedSender;
 
-use super::{Component, FocusableWidget};
-use crate::{action::Action, data::LayerInfo, tui::Frame};
+use super::{feature_diff::change_color, Component, FocusableWidget};
+use crate::{
+    action::Action,
+    data::{
+        self,
+        diff::FeatureChangeKind,
+        extent::Extent,
+        geometries::MapGeometries,
+        srs::{reproject_geometry, wgs84},
+        GeomFieldInfo, LayerInfo, SpatialFilter,
+    },
+    mode::Mode,
+    tui::Frame,
+};
+
+/// Narrowest view, in degrees, about a meter at the equator.
+const MIN_VIEW_SPAN: f64 = 1e-5;
+
+fn world() -> Extent {
+    Extent {
+        xmin: -180.,
+        ymin: -90.,
+        xmax: 180.,
+        ymax: 90.,
+    }
+}
 
-#[derive(Debug, Focus, Clone)]
+#[derive(Debug, Default, Focus, Clone)]
 pub struct PositionMap {
+    command_tx: Option<UnboundedSender<Action>>,
     pub xmin: f64,
     pub xmax: f64,
     pub ymin: f64,
     pub ymax: f64,
+    pub geom_fields: Vec<GeomFieldInfo>,
+    /// Dataset and layer index of the selected layer
+    layer: Option<(usize, usize)>,
+    /// Index and CRS of the geometry field shown
+    geom_field: usize,
+    srs: data::srs::Srs,
+    /// Spatial filter of the layer, as longitude/latitude
+    filter: Option<Extent>,
+    /// First corner of the box being drawn, as longitude/latitude
+    bbox_start: Option<(f64, f64)>,
+    /// Keyboard or mouse position while drawing a box or picking a feature
+    cursor: Option<(f64, f64)>,
+    /// Whether the keyboard cursor picks a feature rather than drawing a box
+    picking: bool,
+    /// Box being typed by the user, in the CRS of the layer
+    bbox_input: Option<String>,
+    message: Option<String>,
+    /// Geometries of the geometry field shown, read by the GDAL thread
+    geometries: MapGeometries,
+    /// Geometries of the feature diff being shown, as longitude/latitude
+    changes: Vec<(FeatureChangeKind, geo::Geometry)>,
+    /// Geometry problem selected in the geometries panel, as longitude/latitude
+    problem: Option<(f64, f64)>,
+    /// Part of the world shown, as longitude/latitude, all of it if `None`
+    view: Option<Extent>,
+    /// Where the map was last drawn, to place mouse events
+    area: Rect,
     pub is_focused: bool,
+    pub is_loading: bool,
+}
+
+/// Draw `geometry`, given as longitude/latitude: vertices of points, segments of the rest.
+fn draw_geometry(ctx: &mut Context, geometry: &geo::Geometry, color: Color) {
+    let mut segments = |lines: &mut dyn Iterator<Item = geo::Line>| {
+        for line in lines {
+            ctx.draw(&canvas::Line {
+                x1: line.start.x,
+                y1: line.start.y,
+                x2: line.end.x,
+                y2: line.end.y,
+                color,
+            });
+        }
+    };
+    match geometry {
+        geo::Geometry::Point(_) | geo::Geometry::MultiPoint(_) => {
+            let coords: Vec<(f64, f64)> = geometry.coords_iter().map(|c| c.x_y()).collect();
+            ctx.draw(&Points {
+                coords: &coords,
+                color,
+            });
+        }
+        geo::Geometry::Line(g) => segments(&mut g.lines_iter()),
+        geo::Geometry::LineString(g) => segments(&mut g.lines_iter()),
+        geo::Geometry::MultiLineString(g) => segments(&mut g.lines_iter()),
+        geo::Geometry::Polygon(g) => segments(&mut g.lines_iter()),
+        geo::Geometry::MultiPolygon(g) => segments(&mut g.lines_iter()),
+        geo::Geometry::Rect(g) => segments(&mut g.to_polygon().lines_iter()),
+        geo::Geometry::Triangle(g) => segments(&mut g.to_polygon().lines_iter()),
+        geo::Geometry::GeometryCollection(g) => {
+            for geometry in g {
+                draw_geometry(ctx, geometry, color);
+            }
+        }
+    }
 }
 
 impl PositionMap {
     pub fn from_layerinfo(li: &LayerInfo) -> Self {
-        let reproj = li.extent.clone().reproject(
-            SpatialRef::from_proj4(&li.srs.proj4).unwrap(),
-            SpatialRef::from_epsg(4326).unwrap(),
+        let mut position_map = Self {
+            geom_fields: li.geom_fields.clone(),
+            srs: li.srs.clone(),
+            ..Default::default()
+        };
+        position_map.set_extent(li.extent.as_ref(), &li.srs);
+        position_map
+    }
+
+    /// Place `extent`, expressed in `srs`, on the map. Nothing is drawn without an extent or a
+    /// CRS.
+    fn set_extent(&mut self, extent: Option<&Extent>, srs: &data::srs::Srs) {
+        let reproj = extent
+            .and_then(|extent| {
+                srs.spatial_ref()
+                    .and_then(|ssr| extent.reproject(&ssr, &wgs84()?))
+                    .ok()
+            })
+            .unwrap_or_default();
+        self.xmin = reproj.xmin;
+        self.xmax = reproj.xmax;
+        self.ymin = reproj.ymin;
+        self.ymax = reproj.ymax;
+    }
+
+    /// Show the spatial filter of the layer, given in the CRS of the geometry field it applies
+    /// to, whichever field is shown.
+    fn set_filter(&mut self, filter: Option<&SpatialFilter>) {
+        self.filter = filter.and_then(|f| {
+            self.geom_fields
+                .get(f.geom_field)?
+                .srs
+                .spatial_ref()
+                .and_then(|ssr| f.extent.reproject(&ssr, &wgs84()?))
+                .ok()
+        });
+    }
+
+    /// Longitude and latitude bounds of the map.
+    fn bounds(&self) -> Extent {
+        self.view.clone().unwrap_or_else(world)
+    }
+
+    /// Show `view`, moved back within the world. The whole world if it is wider or taller.
+    fn set_view(&mut self, view: Extent) {
+        let w = world();
+        if view.xmax - view.xmin >= w.xmax - w.xmin || view.ymax - view.ymin >= w.ymax - w.ymin {
+            self.view = None;
+            return;
+        }
+        let dx = (w.xmin - view.xmin).max(0.) + (w.xmax - view.xmax).min(0.);
+        let dy = (w.ymin - view.ymin).max(0.) + (w.ymax - view.ymax).min(0.);
+        self.view = Some(Extent::from_corners(
+            (view.xmin + dx, view.ymin + dy),
+            (view.xmax + dx, view.ymax + dy),
+        ));
+    }
+
+    /// Show `extent`, as longitude/latitude, with a margin and the proportions of the map.
+    fn fit_view(&mut self, extent: &Extent) {
+        // Cells are about twice as tall as wide
+        let ratio = match (self.area.width, self.area.height) {
+            (0, _) | (_, 0) => 2.,
+            (width, height) => width as f64 / (height as f64 * 2.),
+        };
+        let mut width = (extent.xmax - extent.xmin).max(MIN_VIEW_SPAN) * 1.1;
+        let mut height = (extent.ymax - extent.ymin).max(MIN_VIEW_SPAN) * 1.1;
+        if width < height * ratio {
+            width = height * ratio;
+        } else {
+            height = width / ratio;
+        }
+        let (x, y) = (
+            (extent.xmin + extent.xmax) / 2.,
+            (extent.ymin + extent.ymax) / 2.,
+        );
+        self.set_view(Extent::from_corners(
+            (x - width / 2., y - height / 2.),
+            (x + width / 2., y + height / 2.),
+        ));
+    }
+
+    /// Scale the view by `factor` about its center, below 1 to zoom in.
+    fn zoom(&mut self, factor: f64) {
+        let b = self.bounds();
+        let (width, height) = (b.xmax - b.xmin, b.ymax - b.ymin);
+        if factor < 1. && width.min(height) * factor < MIN_VIEW_SPAN {
+            return;
+        }
+        let (x, y) = ((b.xmin + b.xmax) / 2., (b.ymin + b.ymax) / 2.);
+        let (dx, dy) = (width * factor / 2., height * factor / 2.);
+        self.set_view(Extent::from_corners((x - dx, y - dy), (x + dx, y + dy)));
+    }
+
+    /// Move the view by fractions of its width and height.
+    fn pan(&mut self, x: f64, y: f64) {
+        let b = self.bounds();
+        let dx = (b.xmax - b.xmin) * x;
+        let dy = (b.ymax - b.ymin) * y;
+        self.set_view(Extent::from_corners(
+            (b.xmin + dx, b.ymin + dy),
+            (b.xmax + dx, b.ymax + dy),
+        ));
+    }
+
+    /// Fit the view to the extent of the layer, if it could be placed on the map.
+    fn zoom_to_layer(&mut self) {
+        let extent = Extent::from_corners((self.xmin, self.ymin), (self.xmax, self.ymax));
+        if extent == Extent::default() {
+            self.message = Some("Layer has no usable CRS".into());
+        } else {
+            self.message = None;
+            self.fit_view(&extent);
+        }
+    }
+
+    /// Longitude and latitude under the terminal cell `column`, `row`, if on the map.
+    fn position(&self, column: u16, row: u16) -> Option<(f64, f64)> {
+        let area = self.area;
+        if column < area.x || column >= area.right() || row < area.y || row >= area.bottom() {
+            return None;
+        }
+        let x = (column - area.x) as f64 + 0.5;
+        let y = (row - area.y) as f64 + 0.5;
+        let b = self.bounds();
+        Some((
+            b.xmin + x / area.width as f64 * (b.xmax - b.xmin),
+            b.ymax - y / area.height as f64 * (b.ymax - b.ymin),
+        ))
+    }
+
+    /// Mark the problem at `position`, in the CRS of the layer, and zoom on it. The view spans
+    /// a tenth of the layer extent, keeping the proportions of the world map.
+    fn locate_problem(&mut self, position: Option<(f64, f64)>) {
+        self.problem = position.and_then(|(x, y)| {
+            // Problems are found in the first geometry field
+            let ssr = self.geom_fields.first()?.srs.spatial_ref().ok()?;
+            let transform = CoordTransform::new(&ssr, &wgs84().ok()?).ok()?;
+            match reproject_geometry(&geo::Point::new(x, y).into(), &transform).ok()? {
+                geo::Geometry::Point(p) => Some(p.x_y()),
+                _ => None,
+            }
+        });
+        self.view = self.problem.map(|(x, y)| {
+            let width = (self.xmax - self.xmin)
+                .max((self.ymax - self.ymin) * 2.)
+                .max(0.01)
+                / 10.;
+            Extent::from_corners(
+                (x - width / 2., y - width / 4.),
+                (x + width / 2., y + width / 4.),
+            )
+        });
+        if let Some(view) = self.view.take() {
+            self.set_view(view);
+        }
+    }
+
+    /// Place the keyboard cursor on the middle of the layer if in view, of the view otherwise.
+    fn start_cursor(&mut self) {
+        let b = self.bounds();
+        let (x, y) = ((self.xmin + self.xmax) / 2., (self.ymin + self.ymax) / 2.);
+        self.cursor = Some(
+            if b.xmin <= x && x <= b.xmax && b.ymin <= y && y <= b.ymax {
+                (x, y)
+            } else {
+                ((b.xmin + b.xmax) / 2., (b.ymin + b.ymax) / 2.)
+            },
         );
-        Self {
-            xmin: reproj.xmin,
-            xmax: reproj.xmax,
-            ymin: reproj.ymin,
-            ymax: reproj.ymax,
-            is_focused: false,
+    }
+
+    /// Read the drawn feature nearest to `position`, within two cells of the map.
+    fn pick_feature(&mut self, position: (f64, f64)) -> Option<Action> {
+        let (d, l) = self.layer?;
+        let b = self.bounds();
+        let tolerance = 2.
+            * ((b.xmax - b.xmin) / self.area.width.max(1) as f64)
+                .max((b.ymax - b.ymin) / self.area.height.max(1) as f64);
+        let point = geo::Point::from(position);
+        let picked = self
+            .geometries
+            .features
+            .iter()
+            .map(|(fid, geometry)| (*fid, point.euclidean_distance(geometry)))
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        match picked {
+            Some((fid, _)) => {
+                self.message = None;
+                Some(Action::ReadFeature(d, l, fid))
+            }
+            None => {
+                self.message = Some("No feature here".into());
+                None
+            }
+        }
+    }
+
+    /// Turn the box drawn from `bbox_start` to `cursor` into a spatial filter on the layer.
+    fn apply_drawn_bbox(&mut self) -> Option<Action> {
+        let (start, end) = (self.bbox_start.take()?, self.cursor.take()?);
+        let (d, l) = self.layer?;
+        let extent = Extent::from_corners(start, end);
+        match self
+            .srs
+            .spatial_ref()
+            .and_then(|dsr| extent.reproject(&wgs84()?, &dsr))
+        {
+            Ok(extent) => Some(self.spatial_filter(d, l, extent)),
+            Err(_) => {
+                self.message = Some("Layer has no usable CRS, type the box instead".into());
+                None
+            }
+        }
+    }
+
+    fn spatial_filter(&self, d: usize, l: usize, extent: Extent) -> Action {
+        Action::SetSpatialFilter(
+            d,
+            l,
+            Some(SpatialFilter {
+                geom_field: self.geom_field,
+                extent,
+            }),
+        )
+    }
+
+    /// Parse `xmin ymin xmax ymax`, separated by spaces or commas.
+    fn parse_bbox(input: &str) -> Option<Extent> {
+        let values = input
+            .split([' ', ','])
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse::<f64>().ok())
+            .collect::<Option<Vec<f64>>>()?;
+        match values[..] {
+            [xmin, ymin, xmax, ymax] => Some(Extent::from_corners((xmin, ymin), (xmax, ymax))),
+            _ => None,
+        }
+    }
+
+    fn title(&self) -> String {
+        let b = self.bounds();
+        // Enough decimals to tell the bounds apart
+        let span = (b.xmax - b.xmin).min(b.ymax - b.ymin);
+        let decimals = (2. - span.log10()).clamp(0., 6.) as usize;
+        let mut title = format!(
+            "Position Map [{:.decimals$}, {:.decimals$}, {:.decimals$}, {:.decimals$}]",
+            b.xmin, b.ymin, b.xmax, b.ymax
+        );
+        if self.is_loading {
+            title.push_str(" (loading…)");
+        }
+        let (n, total) = (self.geometries.features.len(), self.geometries.count);
+        if total > n as u64 {
+            title.push_str(&format!(" ({n} of {total} geometries drawn)"));
+        }
+        if let Some(input) = &self.bbox_input {
+            title.push_str(&format!(" xmin ymin xmax ymax: {input}▏"));
+        } else if self.picking {
+            title.push_str(" <enter> pick feature, <esc> cancel");
+        } else if self.bbox_start.is_some() {
+            title.push_str(" <enter> second corner, <esc> cancel");
+        } else if self.cursor.is_some() {
+            title.push_str(" <enter> first corner, <esc> cancel");
+        } else if let Some(message) = &self.message {
+            title.push_str(&format!(" {message}"));
+        }
+        title
+    }
+
+    fn handle_bbox_input(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let Some(input) = &mut self.bbox_input else {
+            return Ok(None);
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => match (Self::parse_bbox(input), self.layer) {
+                (Some(extent), Some((d, l))) => {
+                    self.bbox_input = None;
+                    self.message = None;
+                    if let Some(tx) = &self.command_tx {
+                        tx.send(self.spatial_filter(d, l, extent))?;
+                    }
+                    return Ok(Some(Action::ExitInput));
+                }
+                _ => self.message = Some("Expected four numbers".into()),
+            },
+            KeyCode::Esc => {
+                self.bbox_input = None;
+                return Ok(Some(Action::ExitInput));
+            }
+            _ => {}
         }
+        Ok(None)
     }
 }
 
-impl FocusableWidget for PositionMap {}
+impl FocusableWidget for PositionMap {
+    fn mode(&self) -> Mode {
+        Mode::PositionMap
+    }
+}
 
 impl Component for PositionMap {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.command_tx = Some(tx);
+        Ok(())
+    }
+
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if self.bbox_input.is_some() {
+            return self.handle_bbox_input(key);
+        }
+        let Some((x, y)) = self.cursor else {
+            return Ok(None);
+        };
+        // One cell of the map, ten with shift
+        let b = self.bounds();
+        let mut step = (
+            (b.xmax - b.xmin) / self.area.width.max(1) as f64,
+            (b.ymax - b.ymin) / self.area.height.max(1) as f64,
+        );
+        if key.modifiers.contains(KeyModifiers::SHIFT) {
+            step = (step.0 * 10., step.1 * 10.);
+        }
+        match key.code {
+            KeyCode::Left => self.cursor = Some(((x - step.0).max(b.xmin), y)),
+            KeyCode::Right => self.cursor = Some(((x + step.0).min(b.xmax), y)),
+            KeyCode::Up => self.cursor = Some((x, (y + step.1).min(b.ymax))),
+            KeyCode::Down => self.cursor = Some((x, (y - step.1).max(b.ymin))),
+            KeyCode::Enter if self.picking => {
+                self.picking = false;
+                let action = self.cursor.take().and_then(|c| self.pick_feature(c));
+                if let (Some(tx), Some(action)) = (&self.command_tx, action) {
+                    tx.send(action)?;
+                }
+                return Ok(Some(Action::ExitInput));
+            }
+            KeyCode::Enter if self.bbox_start.is_none() => self.bbox_start = self.cursor,
+            KeyCode::Enter => {
+                let action = self.apply_drawn_bbox();
+                if let (Some(tx), Some(action)) = (&self.command_tx, action) {
+                    tx.send(action)?;
+                }
+                return Ok(Some(Action::ExitInput));
+            }
+            KeyCode::Esc => {
+                self.bbox_start = None;
+                self.cursor = None;
+                self.picking = false;
+                return Ok(Some(Action::ExitInput));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn handle_mouse_events(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+        if !self.is_focused || self.layer.is_none() || self.bbox_input.is_some() || self.picking {
+            return Ok(None);
+        }
+        let position = self.position(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) if position.is_some() => {
+                self.bbox_start = position;
+                self.cursor = position;
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.bbox_start.is_some() => {
+                self.cursor = position.or(self.cursor);
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.bbox_start.is_some() => {
+                if self.bbox_start != self.cursor {
+                    return Ok(self.apply_drawn_bbox());
+                }
+                // A click without dragging picks a feature
+                self.cursor = None;
+                let picked = self.bbox_start.take().and_then(|p| self.pick_feature(p));
+                return Ok(picked);
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         if let Action::PassLayerInfo(li) = action {
-            let reproj = li.extent.clone().reproject(
-                SpatialRef::from_proj4(&li.srs.proj4).unwrap(),
-                SpatialRef::from_epsg(4326).unwrap(),
-            );
-            self.xmin = reproj.xmin;
-            self.xmax = reproj.xmax;
-            self.ymin = reproj.ymin;
-            self.ymax = reproj.ymax;
+            self.is_loading = false;
+            self.set_extent(li.extent.as_ref(), &li.srs);
+            self.geom_fields = li.geom_fields;
+            self.geom_field = 0;
+            self.srs = li.srs;
+            self.geometries = MapGeometries::default();
+            self.set_filter(li.filter.spatial.as_ref());
+            self.problem = None;
+            if let Some((d, l)) = self.layer {
+                return Ok(Some(Action::ReadMapGeometries(d, l, 0)));
+            }
+        } else if let Action::PassRasterInfo(ri) = action {
+            self.is_loading = false;
+            self.set_extent(Some(&ri.extent), &ri.srs);
+            self.geometries = MapGeometries::default();
+            self.geom_fields = vec![];
+            self.layer = None;
+            self.filter = None;
+        } else if let Action::SelectLayer(d, l) = action {
+            self.layer = Some((d, l));
+            self.message = None;
+        } else if let Action::LoadLayer(..) = action {
+            self.is_loading = true;
+        } else if let Action::PassMapGeometries(d, l, geometries) = action {
+            if self.layer == Some((d, l)) && geometries.geom_field == self.geom_field {
+                self.geometries = geometries;
+            }
+        } else if let Action::LocateProblem(position) = action {
+            self.locate_problem(position);
+        } else if let Action::PassFeatureDiff(diff) = action {
+            self.changes = diff
+                .changes
+                .into_iter()
+                .filter_map(|c| Some((c.kind, c.geometry?)))
+                .collect();
+        } else if let Action::CloseFeatureDiff = action {
+            self.changes.clear();
+        } else if let Action::SelectGeomField(i) = action {
+            if let Some(g) = self.geom_fields.get(i).cloned() {
+                self.set_extent(g.extent.as_ref(), &g.srs);
+                self.geom_field = i;
+                self.srs = g.srs;
+                self.geometries = MapGeometries::default();
+                if let Some((d, l)) = self.layer {
+                    return Ok(Some(Action::ReadMapGeometries(d, l, i)));
+                }
+            }
+        } else if let Action::PassLayersExtent(extent) = action {
+            match extent {
+                Some(extent) => self.fit_view(&extent),
+                None => self.message = Some("No dataset could be placed on the map".into()),
+            }
+        } else if !self.is_focused {
+            // Navigation and filtering are for the focused panel
+        } else if let Action::ZoomIn = action {
+            self.zoom(0.5);
+        } else if let Action::ZoomOut = action {
+            self.zoom(2.);
+        } else if let Action::PanLeft = action {
+            self.pan(-0.25, 0.);
+        } else if let Action::PanRight = action {
+            self.pan(0.25, 0.);
+        } else if let Action::PanUp = action {
+            self.pan(0., 0.25);
+        } else if let Action::PanDown = action {
+            self.pan(0., -0.25);
+        } else if let Action::ZoomToWorld = action {
+            self.view = None;
+        } else if let Action::ZoomToLayer = action {
+            self.zoom_to_layer();
+        } else if self.layer.is_none() {
+            // Filtering is on a layer
+        } else if let Action::DrawBbox = action {
+            self.message = None;
+            self.start_cursor();
+            return Ok(Some(Action::EnterInput));
+        } else if let Action::PickFeature = action {
+            self.message = None;
+            self.picking = true;
+            self.start_cursor();
+            return Ok(Some(Action::EnterInput));
+        } else if let Action::TypeBbox = action {
+            self.message = None;
+            self.bbox_input = Some(String::new());
+            return Ok(Some(Action::EnterInput));
+        } else if let Action::ClearSpatialFilter = action {
+            let (d, l) = self.layer.unwrap_or_default();
+            return Ok(Some(Action::SetSpatialFilter(d, l, None)));
         };
         Ok(None)
     }
@@ -61,32 +603,78 @@ impl Component for PositionMap {
             .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
             .split(rect);
 
-        let rect = rects[1];
+        let inner_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
+            .split(rects[1]);
+
+        let rect = inner_rects[0];
 
         let mut block = Block::default()
-            .title(block::Title::from("Position Map").alignment(Alignment::Left))
+            .title(block::Title::from(self.title()).alignment(Alignment::Left))
             .borders(Borders::ALL);
 
         if self.is_focused {
             block = block.border_set(symbols::border::DOUBLE);
         }
+        self.area = block.inner(rect);
+        let bounds = self.bounds();
 
         let map = Canvas::default()
             .block(block)
-            .x_bounds([-180.0, 180.0])
-            .y_bounds([-90.0, 90.0])
+            .x_bounds([bounds.xmin, bounds.xmax])
+            .y_bounds([bounds.ymin, bounds.ymax])
             .paint(|ctx| {
                 ctx.draw(&Map {
                     resolution: MapResolution::High,
                     color: Color::White,
                 });
+                // Whatever follows stays visible over the coastlines
+                ctx.layer();
                 ctx.draw(&Rectangle {
-                    x: self.ymin,
-                    y: self.xmin,
-                    width: self.ymax - self.ymin,
-                    height: self.xmax - self.xmin,
+                    x: self.xmin,
+                    y: self.ymin,
+                    width: self.xmax - self.xmin,
+                    height: self.ymax - self.ymin,
                     color: Color::Red,
                 });
+                for (_, geometry) in &self.geometries.features {
+                    draw_geometry(ctx, geometry, Color::LightCyan);
+                }
+                for (kind, geometry) in &self.changes {
+                    draw_geometry(ctx, geometry, change_color(*kind));
+                }
+                if let Some(filter) = &self.filter {
+                    ctx.draw(&Rectangle {
+                        x: filter.xmin,
+                        y: filter.ymin,
+                        width: filter.xmax - filter.xmin,
+                        height: filter.ymax - filter.ymin,
+                        color: Color::LightYellow,
+                    });
+                }
+                if let (Some(start), Some(end)) = (self.bbox_start, self.cursor) {
+                    let bbox = Extent::from_corners(start, end);
+                    ctx.draw(&Rectangle {
+                        x: bbox.xmin,
+                        y: bbox.ymin,
+                        width: bbox.xmax - bbox.xmin,
+                        height: bbox.ymax - bbox.ymin,
+                        color: Color::LightGreen,
+                    });
+                }
+                if let Some(problem) = self.problem {
+                    ctx.draw(&Points {
+                        coords: &[problem],
+                        color: Color::Magenta,
+                    });
+                }
+                if let Some(cursor) = self.cursor {
+                    ctx.draw(&Points {
+                        coords: &[cursor],
+                        color: Color::LightGreen,
+                    });
+                }
             })
             .marker(Marker::Braille);
         f.render_widget(map, rect);
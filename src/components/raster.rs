@@ -0,0 +1,162 @@
+use color_eyre::eyre::Result;
+use focusable::Focus;
+use ratatui::{prelude::*, widgets::*};
+
+use super::{Component, FocusableWidget};
+use crate::{
+    action::Action,
+    data::raster::{BandInfo, RasterInfo},
+    mode::Mode,
+    tui::Frame,
+};
+
+/// Band information of the selected raster, drawn in place of the `Fields` panel.
+#[derive(Debug, Default, Clone)]
+pub struct Raster {
+    pub rasterinfo: Option<RasterInfo>,
+    pub is_focused: bool,
+}
+
+impl Raster {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn band_row(band: &BandInfo) -> Row<'_> {
+        let no_data = band.no_data.map(|n| n.to_string()).unwrap_or_default();
+        let overviews = band
+            .overviews
+            .iter()
+            .map(|(x, y)| format!("{x}x{y}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        [
+            band.index.to_string(),
+            band.data_type.clone(),
+            no_data,
+            band.color_interpretation.clone(),
+            format!("{}x{}", band.block_size.0, band.block_size.1),
+            overviews,
+            band.compression.clone().unwrap_or_default(),
+        ]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(Style::new().fg(Color::LightCyan))
+    }
+}
+
+// Hidden, and skipped when cycling focus, unless a raster is selected
+impl Focus for Raster {
+    fn can_focus(&self) -> bool {
+        self.rasterinfo.is_some()
+    }
+
+    fn is_focused(&self) -> bool {
+        self.is_focused
+    }
+
+    fn focus(&mut self) {
+        self.is_focused = true;
+    }
+
+    fn blur(&mut self) {
+        self.is_focused = false;
+    }
+}
+
+impl FocusableWidget for Raster {
+    fn mode(&self) -> Mode {
+        Mode::Raster
+    }
+}
+
+impl Component for Raster {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        if let Action::PassRasterInfo(ri) = action {
+            self.rasterinfo = Some(ri);
+        } else if let Action::PassLayerInfo(_) | Action::LoadLayer(..) = action {
+            self.rasterinfo = None;
+        };
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let Some(rasterinfo) = &self.rasterinfo else {
+            return Ok(());
+        };
+
+        let rects = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, 4), Constraint::Ratio(1, 4)])
+            .split(rect);
+
+        let inner_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Ratio(1, 3),
+                Constraint::Length(6),
+                Constraint::Min(0),
+            ])
+            .split(rects[1]);
+
+        let rect = inner_rects[2];
+
+        let mut block = Block::default()
+            .title(block::Title::from("Raster").alignment(Alignment::Right))
+            .borders(Borders::ALL);
+
+        if self.is_focused {
+            block = block.border_set(symbols::border::DOUBLE);
+        }
+
+        let panel_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(3), Constraint::Min(0)])
+            .split(block.inner(rect));
+
+        let geo_transform = rasterinfo
+            .geo_transform
+            .map(|gt| format!("{gt:?}"))
+            .unwrap_or_else(|| "none".into());
+        let summary = Paragraph::new(vec![
+            Line::from(format!("size: {}x{}", rasterinfo.size.0, rasterinfo.size.1)),
+            Line::from(format!("geotransform: {geo_transform}")),
+            Line::from(format!("bands: {}", rasterinfo.bands.len())),
+        ]);
+
+        let header = [
+            "Band",
+            "Type",
+            "NoData",
+            "Color",
+            "Block",
+            "Overviews",
+            "Compression",
+        ]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(Style::default().add_modifier(Modifier::BOLD))
+        .height(1);
+
+        let t = Table::new(
+            rasterinfo.bands.iter().map(Self::band_row),
+            [
+                Constraint::Length(4),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(10),
+                Constraint::Length(9),
+                Constraint::Min(10),
+                Constraint::Min(10),
+            ],
+        )
+        .header(header);
+
+        f.render_widget(block, rect);
+        f.render_widget(summary, panel_rects[0]);
+        f.render_widget(t, panel_rects[1]);
+        Ok(())
+    }
+}
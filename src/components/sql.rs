@@ -0,0 +1,188 @@
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use focusable::Focus;
+use ratatui::{prelude::*, widgets::*};
+
+use super::{Component, FocusableWidget};
+use crate::{
+    action::Action,
+    data::sql::{History, SqlDialect},
+    mode::Mode,
+    tui::Frame,
+    utils,
+};
+
+/// Prompt running a SQL statement on the dataset of the selected layer, drawn over the attribute
+/// table while open. Result sets are added to the layer list as in-memory datasets.
+///
+/// It has no focus of its own: it is opened from the focused panel and reads keys in input mode.
+#[derive(Debug, Default, Clone, Focus)]
+pub struct SqlConsole {
+    is_open: bool,
+    input: String,
+    dialect: SqlDialect,
+    history: History,
+    /// Position in the history while browsing it with the arrow keys
+    history_index: Option<usize>,
+    /// Outcome of the last statement, and whether it is an error
+    message: Option<(String, bool)>,
+    is_running: bool,
+    /// Dataset of the selected layer, the one queried
+    dataset: Option<usize>,
+}
+
+impl SqlConsole {
+    pub fn new() -> Self {
+        Self {
+            history: History::load(utils::get_data_dir().join("sql_history")),
+            ..Default::default()
+        }
+    }
+
+    fn browse(&mut self, index: Option<usize>) {
+        self.history_index = index;
+        self.input = index
+            .and_then(|i| self.history.entries.get(i))
+            .cloned()
+            .unwrap_or_default();
+    }
+}
+
+impl FocusableWidget for SqlConsole {
+    fn mode(&self) -> Mode {
+        Mode::Input
+    }
+}
+
+impl Component for SqlConsole {
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if !self.is_open {
+            return Ok(None);
+        }
+        match key.code {
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Tab => self.dialect = self.dialect.toggle(),
+            KeyCode::Up => {
+                let last = self.history.entries.len().checked_sub(1);
+                let index = match self.history_index {
+                    Some(i) => Some(i.saturating_sub(1)),
+                    None => last,
+                };
+                self.browse(index);
+            }
+            KeyCode::Down => {
+                let index = self
+                    .history_index
+                    .map(|i| i + 1)
+                    .filter(|i| *i < self.history.entries.len());
+                self.browse(index);
+            }
+            KeyCode::Enter if !self.is_running => {
+                let query = self.input.trim().to_string();
+                let Some(dataset) = self.dataset.filter(|_| !query.is_empty()) else {
+                    return Ok(None);
+                };
+                if let Err(e) = self.history.push(&query) {
+                    log::error!("Failed to save the SQL history: {e}");
+                }
+                self.history_index = None;
+                self.is_running = true;
+                self.message = None;
+                return Ok(Some(Action::ExecuteSql(dataset, query, self.dialect)));
+            }
+            KeyCode::Esc => {
+                self.is_open = false;
+                self.message = None;
+                return Ok(Some(Action::ExitInput));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::SelectLayer(d, _) => self.dataset = Some(d),
+            Action::OpenSqlConsole if self.dataset.is_some() => {
+                self.is_open = true;
+                self.history_index = None;
+                self.message = None;
+                return Ok(Some(Action::EnterInput));
+            }
+            Action::SqlResult(result) => {
+                self.is_running = false;
+                if result.is_some() {
+                    self.is_open = false;
+                    self.input.clear();
+                    return Ok(Some(Action::ExitInput));
+                }
+                self.message = Some(("Statement returned no result set".into(), false));
+            }
+            Action::SqlFailed(e) => {
+                self.is_running = false;
+                self.message = Some((e, true));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        if !self.is_open {
+            return Ok(());
+        }
+
+        let rects = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
+            .split(rect);
+
+        let inner_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
+            .split(rects[1]);
+
+        let rect = inner_rects[1];
+
+        let title = if self.is_running {
+            format!("SQL ({}, running…)", self.dialect)
+        } else {
+            format!("SQL ({})", self.dialect)
+        };
+        let block = Block::default()
+            .title(block::Title::from(title).alignment(Alignment::Left))
+            .borders(Borders::ALL)
+            .border_set(symbols::border::DOUBLE);
+
+        let mut lines = vec![Line::from(format!("{}▏", self.input)).fg(Color::LightYellow)];
+        if let Some((message, is_error)) = &self.message {
+            lines.push(Line::default());
+            let color = if *is_error {
+                Color::LightRed
+            } else {
+                Color::DarkGray
+            };
+            lines.push(Line::from(message.as_str()).fg(color));
+        }
+
+        let panel_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Min(0), Constraint::Length(1)])
+            .split(block.inner(rect));
+
+        let hint = Line::from("<enter> run  <tab> dialect  <up>/<down> history  <esc> close")
+            .fg(Color::DarkGray);
+
+        f.render_widget(Clear, rect);
+        f.render_widget(block, rect);
+        f.render_widget(
+            Paragraph::new(lines).wrap(Wrap { trim: false }),
+            panel_rects[0],
+        );
+        f.render_widget(hint, panel_rects[1]);
+        Ok(())
+    }
+}
@@ -8,14 +8,52 @@ use ratatui::{prelude::*, widgets::*};
 use tui_scrollview::{self, ScrollView, ScrollViewState};
 
 use super::{Component, FocusableWidget};
-use crate::{action::Action, data::LayerInfo, tui::Frame};
+use crate::{
+    action::Action,
+    data::{self, GeomFieldInfo, LayerInfo},
+    mode::Mode,
+    tui::Frame,
+};
+
+/// Encoding of the CRS definition shown below its properties.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SrsFormat {
+    #[default]
+    Wkt1,
+    Wkt2,
+    ProjJson,
+    Proj,
+}
+
+impl SrsFormat {
+    fn next(self) -> Self {
+        match self {
+            SrsFormat::Wkt1 => SrsFormat::Wkt2,
+            SrsFormat::Wkt2 => SrsFormat::ProjJson,
+            SrsFormat::ProjJson => SrsFormat::Proj,
+            SrsFormat::Proj => SrsFormat::Wkt1,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SrsFormat::Wkt1 => "WKT1",
+            SrsFormat::Wkt2 => "WKT2_2019",
+            SrsFormat::ProjJson => "PROJJSON",
+            SrsFormat::Proj => "PROJ",
+        }
+    }
+}
 
 #[derive(Debug, Default, Clone, Focus)]
 pub struct Srs {
-    pub name: String,
-    pub wkt: String,
-    pub proj4: String,
+    pub srs: data::srs::Srs,
+    pub format: SrsFormat,
+    pub geom_fields: Vec<GeomFieldInfo>,
+    /// Index of the geometry field shown
+    pub geom_field: usize,
     pub is_focused: bool,
+    pub is_loading: bool,
     pub state: ScrollViewState,
 }
 
@@ -37,22 +75,73 @@ impl Srs {
 
     pub fn from_layerinfo(li: &LayerInfo) -> Self {
         Srs {
-            name: li.srs.name.clone(),
-            wkt: li.srs.wkt.clone(),
-            proj4: li.srs.proj4.clone(),
-            is_focused: false,
-            state: Default::default(),
+            srs: li.srs.clone(),
+            geom_fields: li.geom_fields.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn set_srs(&mut self, srs: data::srs::Srs) {
+        self.srs = srs;
+        self.state = Default::default();
+    }
+
+    /// Properties of the CRS, then its definition in the chosen format.
+    fn text(&self) -> String {
+        let s = &self.srs;
+        if s.wkt.is_empty() {
+            return String::from("No CRS");
+        }
+        let unknown = |v: &Option<String>| v.clone().unwrap_or_else(|| "unknown".into());
+        let axes: Vec<String> = s
+            .axes
+            .iter()
+            .map(|a| format!("{} ({})", a.name, a.orientation))
+            .collect();
+        let mut text = format!("name: {}\n", s.name);
+        text.push_str(&format!("authority: {}\n", unknown(&s.authority)));
+        text.push_str(&format!("type: {}\n", s.kind));
+        text.push_str(&format!("datum: {}\n", unknown(&s.datum)));
+        text.push_str(&format!("ellipsoid: {}\n", unknown(&s.ellipsoid)));
+        text.push_str(&format!("prime meridian: {}\n", unknown(&s.prime_meridian)));
+        text.push_str(&format!("axes: {}\n", axes.join(", ")));
+        text.push_str(&format!("units: {}\n", unknown(&s.unit)));
+        match &s.area_of_use {
+            Some(a) => text.push_str(&format!(
+                "area of use: {}\nbbox: {}, {}, {}, {}\n",
+                a.name, a.west, a.south, a.east, a.north
+            )),
+            None => text.push_str("area of use: unknown\n"),
         }
+        let definition = match self.format {
+            SrsFormat::Wkt1 => &s.wkt,
+            SrsFormat::Wkt2 => &s.wkt2,
+            SrsFormat::ProjJson => &s.projjson,
+            SrsFormat::Proj => &s.proj4,
+        };
+        text.push_str(&format!("\n{}:\n{definition}\n", self.format.label()));
+        text
+    }
+
+    fn title(&self) -> String {
+        let mut title = format!("Srs [{}]", self.format.label());
+        if self.geom_fields.len() > 1 {
+            let name = &self.geom_fields[self.geom_field].name;
+            let (i, n) = (self.geom_field + 1, self.geom_fields.len());
+            title.push_str(&format!(" [{name} {i}/{n}]"));
+        }
+        if self.is_loading {
+            title.push_str(" (loading…)");
+        }
+        title
     }
 
     pub fn line_count(&self) -> u16 {
-        format!("{}\n{}\n{}\n", self.name, self.wkt, self.proj4)
-            .lines()
-            .count() as u16
+        self.text().lines().count() as u16
     }
 
     pub fn line_width(&self) -> u16 {
-        let line: String = format!("{}\n{}\n{}\n", self.name, self.wkt, self.proj4);
+        let line: String = self.text();
         let result = line
             .lines()
             //.filter_map(|l| Some(l))
@@ -61,14 +150,33 @@ impl Srs {
     }
 }
 
-impl FocusableWidget for Srs {}
+impl FocusableWidget for Srs {
+    fn mode(&self) -> Mode {
+        Mode::Srs
+    }
+}
 
 impl Component for Srs {
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         if let Action::PassLayerInfo(li) = action {
-            self.name = li.srs.name;
-            self.proj4 = li.srs.proj4;
-            self.wkt = li.srs.wkt;
+            self.is_loading = false;
+            self.set_srs(li.srs);
+            self.geom_fields = li.geom_fields;
+            self.geom_field = 0;
+        } else if let Action::PassRasterInfo(ri) = action {
+            self.is_loading = false;
+            self.set_srs(ri.srs);
+            self.geom_fields = vec![];
+            self.geom_field = 0;
+        } else if let Action::LoadLayer(..) = action {
+            self.is_loading = true;
+        } else if let Action::SelectGeomField(i) = action {
+            if let Some(srs) = self.geom_fields.get(i).map(|g| g.srs.clone()) {
+                self.set_srs(srs);
+                self.geom_field = i;
+            }
+        } else if !self.is_focused {
+            // Scrolling is for the focused panel only
         } else if let Action::ScrollDown = action {
             self.state.scroll_down();
         } else if let Action::ScrollUp = action {
@@ -77,6 +185,14 @@ impl Component for Srs {
             self.state.scroll_left();
         } else if let Action::ScrollRight = action {
             self.state.scroll_right();
+        } else if let Action::NextSrsFormat = action {
+            self.format = self.format.next();
+            self.state = Default::default();
+        } else if let Action::NextGeomField = action {
+            if self.geom_fields.len() > 1 {
+                let next = (self.geom_field + 1) % self.geom_fields.len();
+                return Ok(Some(Action::SelectGeomField(next)));
+            }
         };
         Ok(None)
     }
@@ -99,18 +215,14 @@ impl Component for Srs {
         let rect = inner_rects[0];
 
         let mut block = Block::default()
-            .title(block::Title::from("Srs").alignment(Alignment::Right))
+            .title(block::Title::from(self.title()).alignment(Alignment::Right))
             .borders(Borders::ALL);
 
         if self.is_focused {
             block = block.border_set(symbols::border::DOUBLE);
         }
 
-        let srs_view = Paragraph::new(format!(
-            "name: {}\nwkt: {}\nproj4: {}\n",
-            self.name, self.wkt, self.proj4
-        ))
-        .block(block);
+        let srs_view = Paragraph::new(self.text()).block(block);
         /*   let l = List::new(self.items.clone())
         .block(block)
         .highlight_symbol(">> ")
@@ -125,7 +237,7 @@ impl Component for Srs {
         let mut scroll_view = ScrollView::new(Size::new(self.line_width(), self.line_count()));
         scroll_view.render_widget(
             srs_view,
-            Rect::new(0, 0, self.line_width() - 1, self.line_count()),
+            Rect::new(0, 0, self.line_width().saturating_sub(1), self.line_count()),
         );
         f.render_stateful_widget(scroll_view, rect, &mut self.state);
         Ok(())
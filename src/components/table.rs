@@ -0,0 +1,351 @@
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use focusable::Focus;
+use ratatui::{prelude::*, widgets::*};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{Component, FocusableWidget};
+use crate::{
+    action::Action,
+    data::features::{FeaturePage, PAGE_SIZE},
+    mode::Mode,
+    tui::Frame,
+};
+
+const MAX_COLUMN_WIDTH: u16 = 24;
+
+/// Pages through the features of the selected layer.
+#[derive(Debug, Default, Clone, Focus)]
+pub struct AttributeTable {
+    command_tx: Option<UnboundedSender<Action>>,
+    /// Dataset and layer index of the selected layer
+    layer: Option<(usize, usize)>,
+    /// Unknown until the information of the selected layer arrives, paging waits for it
+    feature_count: Option<u64>,
+    page: FeaturePage,
+    state: TableState,
+    /// Selected column, the FID column excluded
+    column: usize,
+    /// First column shown right of the frozen FID column
+    column_offset: usize,
+    /// FID being typed by the user
+    fid_input: Option<String>,
+    message: Option<String>,
+    pub is_focused: bool,
+    pub is_loading: bool,
+}
+
+impl AttributeTable {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn read(&mut self, offset: u64) -> Option<Action> {
+        let (d, l) = self.layer?;
+        self.is_loading = true;
+        Some(Action::ReadFeatures(d, l, offset))
+    }
+
+    fn next_row(&mut self) -> Option<Action> {
+        let i = self.state.selected().unwrap_or(0);
+        if i + 1 < self.page.rows.len() {
+            self.state.select(Some(i + 1));
+            None
+        } else if self.page.offset + (self.page.rows.len() as u64) < self.feature_count? {
+            self.state.select(Some(0));
+            self.read(self.page.offset + PAGE_SIZE as u64)
+        } else {
+            None
+        }
+    }
+
+    fn previous_row(&mut self) -> Option<Action> {
+        match self.state.selected() {
+            Some(i) if i > 0 => {
+                self.state.select(Some(i - 1));
+                None
+            }
+            _ if self.page.offset > 0 => {
+                let offset = self.page.offset.saturating_sub(PAGE_SIZE as u64);
+                // Land on the last row of the previous page
+                self.state
+                    .select(Some((self.page.offset - offset) as usize - 1));
+                self.read(offset)
+            }
+            _ => None,
+        }
+    }
+
+    fn next_page(&mut self) -> Option<Action> {
+        let offset = self.page.offset + PAGE_SIZE as u64;
+        if offset < self.feature_count? {
+            self.state.select(Some(0));
+            self.read(offset)
+        } else {
+            None
+        }
+    }
+
+    fn previous_page(&mut self) -> Option<Action> {
+        if self.page.offset > 0 {
+            self.state.select(Some(0));
+            self.read(self.page.offset.saturating_sub(PAGE_SIZE as u64))
+        } else {
+            None
+        }
+    }
+
+    fn title(&self) -> String {
+        let mut title = String::from("Attribute table");
+        if !self.page.rows.is_empty() {
+            let first = self.page.offset + 1;
+            let last = self.page.offset + self.page.rows.len() as u64;
+            let count = self
+                .feature_count
+                .map_or_else(|| "?".to_string(), |c| c.to_string());
+            title.push_str(&format!(" {first}-{last}/{count}"));
+        }
+        if self.is_loading {
+            title.push_str(" (loading…)");
+        }
+        if let Some(input) = &self.fid_input {
+            title.push_str(&format!(" FID: {input}▏"));
+        } else if let Some(message) = &self.message {
+            title.push_str(&format!(" {message}"));
+        }
+        title
+    }
+
+    /// Width of each column, FID column first, from the header and the values of the page.
+    fn column_widths(&self) -> Vec<u16> {
+        let fid_width = self
+            .page
+            .rows
+            .iter()
+            .filter_map(|r| r.fid)
+            .map(|fid| fid.to_string().len())
+            .chain(["FID".len()])
+            .max()
+            .unwrap_or_default();
+        let widths = self.page.columns.iter().enumerate().map(|(i, name)| {
+            self.page
+                .rows
+                .iter()
+                .map(|r| r.values.get(i).cloned().flatten().unwrap_or_default().len())
+                .chain([name.len()])
+                .max()
+                .unwrap_or_default()
+        });
+        [fid_width]
+            .into_iter()
+            .chain(widths)
+            .map(|w| (w as u16).min(MAX_COLUMN_WIDTH))
+            .collect()
+    }
+
+    /// Scroll horizontally so that the selected column fits in `width`.
+    fn scroll_to_column(&mut self, widths: &[u16], width: u16) {
+        if self.column < self.column_offset {
+            self.column_offset = self.column;
+        }
+        let visible = |offset: usize| -> u16 {
+            widths[0]
+                + widths[offset + 1..=self.column + 1]
+                    .iter()
+                    .map(|w| w + 1)
+                    .sum::<u16>()
+        };
+        while self.column_offset < self.column && visible(self.column_offset) > width {
+            self.column_offset += 1;
+        }
+    }
+}
+
+impl FocusableWidget for AttributeTable {
+    fn mode(&self) -> Mode {
+        Mode::AttributeTable
+    }
+}
+
+impl Component for AttributeTable {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.command_tx = Some(tx);
+        Ok(())
+    }
+
+    fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let Some(input) = &mut self.fid_input else {
+            return Ok(None);
+        };
+        match key.code {
+            KeyCode::Char(c) if c.is_ascii_digit() => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let fid = input.parse::<u64>().ok();
+                self.fid_input = None;
+                if let (Some(tx), Some((d, l)), Some(fid)) = (&self.command_tx, self.layer, fid) {
+                    self.is_loading = true;
+                    tx.send(Action::FindFeature(d, l, fid))?;
+                }
+                return Ok(Some(Action::ExitInput));
+            }
+            KeyCode::Esc => {
+                self.fid_input = None;
+                return Ok(Some(Action::ExitInput));
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::SelectLayer(d, l) => {
+                self.layer = Some((d, l));
+                self.feature_count = None;
+                self.page = FeaturePage::default();
+                self.state.select(Some(0));
+                self.column = 0;
+                self.column_offset = 0;
+                self.message = None;
+                Ok(self.read(0))
+            }
+            Action::PassRasterInfo(_) => {
+                self.layer = None;
+                self.feature_count = None;
+                self.page = FeaturePage::default();
+                Ok(None)
+            }
+            Action::PassLayerInfo(li) => {
+                self.feature_count = Some(li.feature_number);
+                Ok(None)
+            }
+            Action::PassFeaturePage(d, l, page) if self.layer == Some((d, l)) => {
+                self.is_loading = false;
+                self.message = None;
+                let last = page.rows.len().saturating_sub(1);
+                self.state
+                    .select(Some(self.state.selected().unwrap_or(0).min(last)));
+                self.page = page;
+                Ok(None)
+            }
+            Action::FeatureNotFound(fid) => {
+                self.is_loading = false;
+                self.message = Some(format!("FID {fid} not found"));
+                Ok(None)
+            }
+            Action::NextRow if self.is_focused => Ok(self.next_row()),
+            Action::PreviousRow if self.is_focused => Ok(self.previous_row()),
+            Action::NextPage if self.is_focused => Ok(self.next_page()),
+            Action::PreviousPage if self.is_focused => Ok(self.previous_page()),
+            Action::NextColumn if self.is_focused => {
+                if self.column + 1 < self.page.columns.len() {
+                    self.column += 1;
+                }
+                Ok(None)
+            }
+            Action::PreviousColumn if self.is_focused => {
+                self.column = self.column.saturating_sub(1);
+                Ok(None)
+            }
+            Action::ShowFeature if self.is_focused => {
+                let fid = self
+                    .state
+                    .selected()
+                    .and_then(|i| self.page.rows.get(i))
+                    .and_then(|r| r.fid);
+                match (self.layer, fid) {
+                    (Some((d, l)), Some(fid)) => Ok(Some(Action::ReadFeature(d, l, fid))),
+                    _ => Ok(None),
+                }
+            }
+            Action::JumpToFid
+                if self.is_focused && self.layer.is_some() && self.feature_count.is_some() =>
+            {
+                self.fid_input = Some(String::new());
+                self.state.select(Some(0));
+                Ok(Some(Action::EnterInput))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+        let rects = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
+            .split(rect);
+
+        let inner_rects = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(50), Constraint::Fill(1)])
+            .split(rects[1]);
+
+        let rect = inner_rects[1];
+
+        let mut block = Block::default()
+            .title(block::Title::from(self.title()).alignment(Alignment::Left))
+            .borders(Borders::ALL);
+
+        if self.is_focused {
+            block = block.border_set(symbols::border::DOUBLE);
+        }
+
+        let widths = self.column_widths();
+        if !self.page.columns.is_empty() {
+            self.scroll_to_column(&widths, block.inner(rect).width);
+        }
+        let visible = self.column_offset..self.page.columns.len();
+
+        let header = ["FID"]
+            .into_iter()
+            .chain(
+                self.page.columns[visible.clone()]
+                    .iter()
+                    .map(String::as_str),
+            )
+            .map(Cell::from)
+            .collect::<Row>()
+            .style(Style::default().add_modifier(Modifier::BOLD))
+            .height(1);
+
+        let selected_column = self.column + 1 - self.column_offset;
+        let rows = self.page.rows.iter().map(|row| {
+            let fid = row.fid.map(|f| f.to_string()).unwrap_or_default();
+            let fid = Cell::from(fid).style(Style::new().fg(Color::DarkGray));
+            let values = row.values.get(visible.clone()).unwrap_or(&[]);
+            let values = values.iter().map(|v| match v {
+                Some(v) => Cell::from(v.as_str()),
+                None => Cell::from("NULL").style(Style::new().fg(Color::DarkGray).italic()),
+            });
+            [fid]
+                .into_iter()
+                .chain(values)
+                .enumerate()
+                .map(|(i, cell)| {
+                    if i == selected_column && self.is_focused {
+                        cell.underlined()
+                    } else {
+                        cell
+                    }
+                })
+                .collect::<Row>()
+                .style(Style::new().fg(Color::LightCyan))
+        });
+
+        let constraints = [widths[0]]
+            .into_iter()
+            .chain(widths[1..].iter().skip(self.column_offset).copied())
+            .map(Constraint::Length);
+
+        let t = Table::new(rows, constraints)
+            .header(header)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .block(block);
+
+        f.render_stateful_widget(t, rect, &mut self.state);
+        Ok(())
+    }
+}
@@ -11,7 +11,7 @@ use serde::{
 };
 use serde_json::Value as JsonValue;
 
-use crate::{action::Action, mode::Mode};
+use crate::{action::Action, data::GdalOptions, mode::Mode};
 
 const CONFIG: &str = include_str!("../.config/config.json5");
 
@@ -21,6 +21,8 @@ pub struct AppConfig {
   pub _data_dir: PathBuf,
   #[serde(default)]
   pub _config_dir: PathBuf,
+  #[serde(default)]
+  pub gdal: GdalOptions,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
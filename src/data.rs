@@ -3,21 +3,37 @@ use gdal::{
     errors::GdalError,
     spatial_ref::SpatialRef,
     vector::{Geometry, Layer, LayerAccess},
-    Dataset, DatasetOptions, DriverManager, GdalOpenFlags,
+    Dataset, DatasetOptions, DriverManager, GdalOpenFlags, Metadata,
 };
-use geo::{coord, Point};
 //use geo::{BoundingRect, GeometryCollection};
 //use geodesy::prelude::*;
 //use geozero::ToGeo;
-use fields::Fields;
+use fields::{FieldStatsCollector, Fields};
+use geometries::GeometrySummary;
+use raster::RasterInfo;
 use serde::{Deserialize, Serialize};
 use srs::Srs;
-use std::{borrow::BorrowMut, path::PathBuf};
+use std::{
+    borrow::BorrowMut,
+    collections::HashMap,
+    ffi::{c_int, CStr},
+    path::PathBuf,
+};
 use strum::Display;
+use tokio_util::sync::CancellationToken;
 
+pub mod crs;
+pub mod diff;
+pub mod export;
 pub mod extent;
+pub mod features;
 pub mod fields;
+pub mod geometries;
+pub mod raster;
+pub mod sql;
 pub mod srs;
+pub mod validity;
+pub mod worker;
 
 lazy_static::lazy_static! {
     static ref DRIVERS: Vec<String> = {
@@ -40,53 +56,244 @@ lazy_static::lazy_static! {
     };
 }
 
-fn get_dataset_options() -> DatasetOptions<'static> {
+/// GDAL settings used when opening datasets, from the config file and the command line.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct GdalOptions {
+    /// Dataset open options, as `KEY=VALUE`
+    #[serde(default)]
+    pub open_options: Vec<String>,
+    /// Drivers allowed to open the datasets, all registered drivers if empty
+    #[serde(default)]
+    pub drivers: Vec<String>,
+    /// GDAL configuration options
+    #[serde(default)]
+    pub config_options: HashMap<String, String>,
+}
+
+impl GdalOptions {
+    /// Put `other` on top of `self`: its open options and drivers replace those of `self` when
+    /// given, its config options win over conflicting ones.
+    pub fn merge(mut self, other: GdalOptions) -> Self {
+        if !other.open_options.is_empty() {
+            self.open_options = other.open_options;
+        }
+        if !other.drivers.is_empty() {
+            self.drivers = other.drivers;
+        }
+        self.config_options.extend(other.config_options);
+        self
+    }
+
+    pub fn apply_config_options(&self) -> Result<(), GdalError> {
+        for (key, value) in &self.config_options {
+            gdal::config::set_config_option(key, value)?;
+        }
+        Ok(())
+    }
+}
+
+fn get_dataset_options<'a>(
+    open_options: &'a [&'a str],
+    drivers: &'a [&'a str],
+) -> DatasetOptions<'a> {
     DatasetOptions {
-        open_flags: GdalOpenFlags::GDAL_OF_VECTOR,
-        allowed_drivers: Some(&DRIVERS_STR),
-        open_options: None,
+        open_flags: GdalOpenFlags::GDAL_OF_VECTOR | GdalOpenFlags::GDAL_OF_RASTER,
+        allowed_drivers: if drivers.is_empty() {
+            Some(&DRIVERS_STR)
+        } else {
+            Some(drivers)
+        },
+        open_options: if open_options.is_empty() {
+            None
+        } else {
+            Some(open_options)
+        },
         sibling_files: None,
     }
 }
 
-pub fn dataset(p: PathBuf) -> Result<Dataset, GdalError> {
-    Dataset::open_ex(p, get_dataset_options())
+pub fn dataset(p: PathBuf, options: &GdalOptions) -> Result<Dataset, GdalError> {
+    if let Some(d) = options.drivers.iter().find(|d| !DRIVERS.contains(d)) {
+        return Err(GdalError::BadArgument(format!("unknown driver '{d}'")));
+    }
+    let open_options: Vec<&str> = options.open_options.iter().map(String::as_str).collect();
+    let drivers: Vec<&str> = options.drivers.iter().map(String::as_str).collect();
+    Dataset::open_ex(p, get_dataset_options(&open_options, &drivers))
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
-pub struct LayerInfo {
+/// Extent and CRS of one geometry field of a layer.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct GeomFieldInfo {
     pub name: String,
+    /// `None` when no feature has a geometry in the field
+    pub extent: Option<Extent>,
+    pub srs: Srs,
+}
+
+impl GeomFieldInfo {
+    /// One entry per geometry field of `layer`, in definition order.
+    pub fn from_layer(layer: &Layer) -> Vec<Self> {
+        layer
+            .defn()
+            .geom_fields()
+            .enumerate()
+            .map(|(i, g)| Self {
+                name: g.name(),
+                extent: Extent::from_geom_field(layer, i),
+                srs: g.spatial_ref().map(|s| Srs::from(&s)).unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+/// Run `f` with GDAL's error output silenced, turning a failure into GDAL's own message,
+/// which says more than the `GdalError` returned by the bindings.
+pub fn gdal_message<T>(f: impl FnOnce() -> Result<T, GdalError>) -> Result<T, String> {
+    unsafe {
+        gdal_sys::CPLErrorReset();
+        gdal_sys::CPLPushErrorHandler(Some(gdal_sys::CPLQuietErrorHandler));
+    }
+    let result = f();
+    let message = unsafe { CStr::from_ptr(gdal_sys::CPLGetLastErrorMsg()) }
+        .to_string_lossy()
+        .into_owned();
+    unsafe { gdal_sys::CPLPopErrorHandler() };
+    result.map_err(|e| {
+        if message.is_empty() {
+            e.to_string()
+        } else {
+            message
+        }
+    })
+}
+
+/// Apply the OGR SQL `filter` to `layer`, or clear its attribute filter if `None`.
+pub fn set_attribute_filter(layer: &mut Layer, filter: Option<&str>) -> Result<(), String> {
+    match filter {
+        Some(filter) => gdal_message(|| layer.set_attribute_filter(filter)),
+        None => {
+            layer.clear_attribute_filter();
+            Ok(())
+        }
+    }
+}
+
+/// Apply `filter` as the spatial filter of `layer`, or clear it if `None`.
+pub fn set_spatial_filter(layer: &mut Layer, filter: Option<&SpatialFilter>) {
+    match filter {
+        Some(f) => unsafe {
+            gdal_sys::OGR_L_SetSpatialFilterRectEx(
+                layer.c_layer(),
+                f.geom_field as c_int,
+                f.extent.xmin,
+                f.extent.ymin,
+                f.extent.xmax,
+                f.extent.ymax,
+            )
+        },
+        None => layer.clear_spatial_filter(),
+    }
+}
+
+/// Bounding box the features of a layer must intersect.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SpatialFilter {
+    /// Index of the geometry field the box applies to
+    pub geom_field: usize,
+    /// Box in the CRS of the geometry field
     pub extent: Extent,
+}
+
+/// Filters applied to a layer.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct LayerFilter {
+    /// OGR SQL WHERE clause
+    pub attribute: Option<String>,
+    pub spatial: Option<SpatialFilter>,
+}
+
+impl LayerFilter {
+    pub fn is_empty(&self) -> bool {
+        self.attribute.is_none() && self.spatial.is_none()
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct LayerInfo {
+    pub name: String,
+    /// `None` when no feature has a geometry
+    pub extent: Option<Extent>,
     pub srs: Srs,
+    /// Extent and CRS of every geometry field, `extent` and `srs` being those of the first
+    pub geom_fields: Vec<GeomFieldInfo>,
     pub fields: Fields,
     pub feature_number: u64,
-    #[serde(skip)]
-    pub geometries: Vec<geo::Geometry>,
+    pub filter: LayerFilter,
+    pub geometry_summary: GeometrySummary,
 }
 
 impl From<&mut Layer<'_>> for LayerInfo {
     fn from(layer: &mut Layer) -> Self {
-        let geometries: Vec<geo::Geometry> = layer
-            .features()
-            .map(|g| {
-                g.geometry()
-                    .unwrap()
-                    .to_geo()
-                    .unwrap_or(geo::Geometry::Point(geo::Point(coord! { x: 0., y: 0. })))
-            })
-            .collect();
+        LayerInfo::load(layer, &CancellationToken::new()).expect("loading was not cancelled")
+    }
+}
+
+impl LayerInfo {
+    /// Read the layer metadata, field statistics and geometry summary, giving up with `None` as
+    /// soon as `cancel` is cancelled. GDAL drivers often report the extent of the whole layer
+    /// whatever its filters, so the extents are taken from the features read instead.
+    pub fn load(layer: &mut Layer, cancel: &CancellationToken) -> Option<Self> {
+        let mut geometry_summary = GeometrySummary::default();
+        let mut field_stats = FieldStatsCollector::new(layer);
+        let mut extents: Vec<Option<Extent>> = vec![None; layer.defn().geom_fields().count()];
+        for f in layer.features() {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            for (i, extent) in extents.iter_mut().enumerate() {
+                let Some(e) = f.geometry_by_index(i).ok().and_then(Extent::from_geometry) else {
+                    continue;
+                };
+                *extent = Some(match extent {
+                    Some(extent) => extent.union(&e),
+                    None => e,
+                });
+            }
+            geometry_summary.add(&f);
+            field_stats.add(&f);
+        }
+        let mut layerinfo = Self::metadata(layer);
+        for (g, extent) in layerinfo.geom_fields.iter_mut().zip(extents) {
+            g.extent = extent;
+        }
+        layerinfo.extent = layerinfo.geom_fields.first().and_then(|g| g.extent.clone());
+        layerinfo.fields.stats = field_stats.build();
+        layerinfo.geometry_summary = geometry_summary;
+        Some(layerinfo)
+    }
+
+    /// Read the schema, feature count, extent and CRS of the layer, as reported by the driver,
+    /// without going through the features.
+    pub fn metadata(layer: &Layer) -> Self {
+        let geom_fields = GeomFieldInfo::from_layer(layer);
         Self {
             name: layer.name(),
-            extent: Extent::from(&*layer),
-            fields: Fields::from(&*layer),
-            srs: Srs::from(&*layer),
+            extent: geom_fields.first().and_then(|g| g.extent.clone()),
+            fields: Fields::from(layer),
+            srs: Srs::from(layer),
+            geom_fields,
             feature_number: layer.feature_count(),
-            geometries,
+            filter: LayerFilter::default(),
+            geometry_summary: GeometrySummary::default(),
         }
     }
-}
 
-impl LayerInfo {
+    /// Mark the layer as filtered by `filter`, which it was loaded with.
+    pub fn filtered(mut self, filter: LayerFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
     pub fn from_dataset(dataset: &Dataset) -> Vec<Self> {
         dataset
             .layers()
@@ -94,3 +301,73 @@ impl LayerInfo {
             .collect()
     }
 }
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DatasetInfo {
+    pub name: String,
+    pub layer_names: Vec<String>,
+    pub rasterinfo: Option<RasterInfo>,
+}
+
+impl From<&Dataset> for DatasetInfo {
+    fn from(dataset: &Dataset) -> Self {
+        Self {
+            name: dataset.description().unwrap_or_default(),
+            layer_names: dataset.layers().map(|l| l.name()).collect(),
+            rasterinfo: RasterInfo::from_dataset(dataset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_merge_gdal_options() {
+        let config = GdalOptions {
+            open_options: vec!["LIST_ALL_TABLES=NO".into()],
+            drivers: vec!["GPKG".into()],
+            config_options: HashMap::from([
+                ("OGR_SQLITE_CACHE".into(), "128".into()),
+                ("CPL_DEBUG".into(), "OFF".into()),
+            ]),
+        };
+        let cli = GdalOptions {
+            drivers: vec!["CSV".into()],
+            config_options: HashMap::from([("CPL_DEBUG".into(), "ON".into())]),
+            ..Default::default()
+        };
+        let merged = config.clone().merge(cli);
+        // Given drivers restrict the allow-list instead of widening it
+        assert_eq!(merged.drivers, vec!["CSV"]);
+        assert_eq!(merged.open_options, vec!["LIST_ALL_TABLES=NO"]);
+        assert_eq!(merged.config_options["CPL_DEBUG"], "ON");
+        assert_eq!(merged.config_options["OGR_SQLITE_CACHE"], "128");
+        let cli = GdalOptions {
+            open_options: vec!["LIST_ALL_TABLES=YES".into()],
+            ..Default::default()
+        };
+        let merged = config.clone().merge(cli);
+        assert_eq!(merged.open_options, vec!["LIST_ALL_TABLES=YES"]);
+        assert_eq!(merged.drivers, vec!["GPKG"]);
+        assert_eq!(config.clone().merge(GdalOptions::default()), config);
+    }
+    #[test]
+    fn test_filtered_extent() {
+        let dataset = Dataset::open("data/my_dataset.gpkg").unwrap();
+        let mut layer = dataset.layer_by_name("us_data").unwrap();
+        let full = LayerInfo::from(&mut layer).extent.unwrap();
+        set_attribute_filter(&mut layer, Some("fid = 1")).unwrap();
+        let layerinfo = LayerInfo::from(&mut layer);
+        let filtered = layerinfo.extent.clone().unwrap();
+        assert_eq!(filtered.union(&full), full);
+        assert!((filtered.xmax - -98.308934).abs() < 1e-6, "{filtered:?}");
+        assert_eq!(layerinfo.geom_fields[0].extent, Some(filtered));
+        // Nothing matching leaves no extent rather than a box at 0, 0
+        set_attribute_filter(&mut layer, Some("fid < 0")).unwrap();
+        assert_eq!(LayerInfo::from(&mut layer).extent, None);
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    ffi::{c_char, CStr},
+    ptr,
+};
+
+use gdal_sys::OSRCRSType;
+use serde::{Deserialize, Serialize};
+
+/// Panel a CRS is searched for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrsTarget {
+    Extent,
+    Export,
+}
+
+/// A CRS of the PROJ database.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct CrsEntry {
+    pub authority: String,
+    pub code: String,
+    pub name: String,
+    /// Geographic 2D, projected, vertical…
+    pub kind: String,
+    pub deprecated: bool,
+    pub area: Option<String>,
+    /// West, south, east and north bounds of the area of use, in degrees
+    pub bbox: Option<[f64; 4]>,
+    pub projection_method: Option<String>,
+    /// Lowercase id, name and area of use, which `search` looks into
+    #[serde(skip)]
+    search_text: String,
+}
+
+impl CrsEntry {
+    /// `AUTHORITY:CODE`, as understood wherever a CRS can be typed.
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.authority, self.code)
+    }
+
+    /// The entry with its search text computed, once for all searches.
+    fn searchable(mut self) -> Self {
+        self.search_text = format!(
+            "{} {} {}",
+            self.id(),
+            self.name,
+            self.area.as_deref().unwrap_or_default()
+        )
+        .to_lowercase();
+        self
+    }
+}
+
+fn kind(t: OSRCRSType::Type) -> &'static str {
+    match t {
+        OSRCRSType::OSR_CRS_TYPE_GEOGRAPHIC_2D => "Geographic 2D",
+        OSRCRSType::OSR_CRS_TYPE_GEOGRAPHIC_3D => "Geographic 3D",
+        OSRCRSType::OSR_CRS_TYPE_GEOCENTRIC => "Geocentric",
+        OSRCRSType::OSR_CRS_TYPE_PROJECTED => "Projected",
+        OSRCRSType::OSR_CRS_TYPE_VERTICAL => "Vertical",
+        OSRCRSType::OSR_CRS_TYPE_COMPOUND => "Compound",
+        _ => "Other",
+    }
+}
+
+fn string(c: *const c_char) -> Option<String> {
+    if c.is_null() {
+        return None;
+    }
+    Some(unsafe { CStr::from_ptr(c) }.to_string_lossy().into())
+}
+
+/// Every CRS of the PROJ database, whatever its authority. Empty if the database is missing.
+pub fn list() -> Vec<CrsEntry> {
+    let mut count = 0;
+    let list =
+        unsafe { gdal_sys::OSRGetCRSInfoListFromDatabase(ptr::null(), ptr::null(), &mut count) };
+    if list.is_null() {
+        return vec![];
+    }
+    let entries = (0..count as usize)
+        .filter_map(|i| {
+            let info = unsafe { (*list.add(i)).as_ref()? };
+            Some(
+                CrsEntry {
+                    authority: string(info.pszAuthName).unwrap_or_default(),
+                    code: string(info.pszCode).unwrap_or_default(),
+                    name: string(info.pszName).unwrap_or_default(),
+                    kind: kind(info.eType).into(),
+                    deprecated: info.bDeprecated != 0,
+                    area: string(info.pszAreaName),
+                    bbox: (info.bBboxValid != 0).then_some([
+                        info.dfWestLongitudeDeg,
+                        info.dfSouthLatitudeDeg,
+                        info.dfEastLongitudeDeg,
+                        info.dfNorthLatitudeDeg,
+                    ]),
+                    projection_method: string(info.pszProjectionMethod),
+                    ..Default::default()
+                }
+                .searchable(),
+            )
+        })
+        .collect();
+    unsafe { gdal_sys::OSRDestroyCRSInfoList(list) };
+    entries
+}
+
+/// Indices of the entries matching every word of `query` in their name, code or area of use.
+/// Exact codes come first and deprecated entries last.
+pub fn search(entries: &[CrsEntry], query: &str) -> Vec<usize> {
+    let query = query.to_lowercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+    let mut matches: Vec<usize> = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| words.iter().all(|w| e.search_text.contains(w)))
+        .map(|(i, _)| i)
+        .collect();
+    let exact = |e: &CrsEntry| {
+        words
+            .iter()
+            .any(|w| *w == e.code || *w == e.id().to_lowercase())
+    };
+    matches.sort_by_key(|i| (!exact(&entries[*i]), entries[*i].deprecated));
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(code: &str, name: &str, area: &str, deprecated: bool) -> CrsEntry {
+        CrsEntry {
+            authority: "EPSG".into(),
+            code: code.into(),
+            name: name.into(),
+            area: Some(area.into()),
+            deprecated,
+            ..Default::default()
+        }
+        .searchable()
+    }
+
+    #[test]
+    fn test_search() {
+        let entries = vec![
+            entry(
+                "21781",
+                "CH1903 / LV03",
+                "Europe - Liechtenstein and Switzerland",
+                false,
+            ),
+            entry(
+                "2056",
+                "CH1903+ / LV95",
+                "Europe - Liechtenstein and Switzerland",
+                false,
+            ),
+            entry("4326", "WGS 84", "World", false),
+            entry("20561", "Old / LV95 variant", "Switzerland", true),
+        ];
+        assert_eq!(search(&entries, "switzerland"), vec![0, 1, 3]);
+        assert_eq!(search(&entries, "LV95 liechtenstein"), vec![1]);
+        // The exact code comes first, deprecated entries last
+        assert_eq!(search(&entries, "2056"), vec![1, 3]);
+        assert_eq!(search(&entries, "epsg:4326"), vec![2]);
+        assert_eq!(search(&entries, "").len(), 4);
+    }
+}
@@ -0,0 +1,688 @@
+use std::collections::{HashMap, VecDeque};
+
+use gdal::{
+    spatial_ref::CoordTransform,
+    vector::{Layer, LayerAccess},
+    Dataset, Metadata,
+};
+use geo::HausdorffDistance;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    extent::Extent,
+    fields::{FieldDefinition, Fields},
+    srs::{reproject_geometry, traditional_axis_order, wgs84, Srs},
+};
+
+/// Extents closer than this, coordinate by coordinate, are considered equal.
+pub const DEFAULT_TOLERANCE: f64 = 1e-9;
+
+/// Tolerance typed by the user, the default one if left empty.
+pub fn parse_tolerance(input: &str) -> Result<f64, String> {
+    match input.trim() {
+        "" => Ok(DEFAULT_TOLERANCE),
+        t => t
+            .parse::<f64>()
+            .ok()
+            .filter(|t| *t >= 0.)
+            .ok_or_else(|| format!("'{t}' is not a positive number")),
+    }
+}
+
+/// What is compared of a layer, read without going through its features.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct LayerSchema {
+    pub name: String,
+    pub fields: Fields,
+    pub srs: Srs,
+    pub extent: Extent,
+    pub feature_count: u64,
+}
+
+impl From<&Layer<'_>> for LayerSchema {
+    fn from(layer: &Layer<'_>) -> Self {
+        Self {
+            name: layer.name(),
+            fields: Fields::from(layer),
+            srs: Srs::from(layer),
+            extent: Extent::from(layer),
+            feature_count: layer.feature_count(),
+        }
+    }
+}
+
+/// A value in the first dataset and in the second.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Change<T> {
+    pub before: T,
+    pub after: T,
+}
+
+/// A field whose definition differs between the two layers.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub name: String,
+    /// Type, width, precision and nullability, as in `String (80.0) NOT NULL`
+    pub definition: Change<String>,
+}
+
+/// Differences between two layers matched by name.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct LayerDiff {
+    pub name: String,
+    /// Name in the second dataset, when the only layers of both datasets are compared
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renamed_to: Option<String>,
+    pub added_fields: Vec<FieldDefinition>,
+    pub removed_fields: Vec<FieldDefinition>,
+    pub changed_fields: Vec<FieldChange>,
+    pub srs: Option<Change<Srs>>,
+    /// Set when a coordinate moved by more than the tolerance
+    pub extent: Option<Change<Extent>>,
+    pub feature_count: Change<u64>,
+}
+
+fn field_definition(f: &FieldDefinition) -> String {
+    let mut s = f.type_name.clone();
+    if let Some(subtype) = &f.subtype {
+        s.push_str(&format!("({subtype})"));
+    }
+    s.push_str(&format!(" ({}.{})", f.width, f.precision));
+    if !f.nullable {
+        s.push_str(" NOT NULL");
+    }
+    s
+}
+
+fn moved(a: &Extent, b: &Extent, tolerance: f64) -> bool {
+    [
+        (a.xmin, b.xmin),
+        (a.ymin, b.ymin),
+        (a.xmax, b.xmax),
+        (a.ymax, b.ymax),
+    ]
+    .iter()
+    .any(|(a, b)| (a - b).abs() > tolerance)
+}
+
+impl LayerDiff {
+    pub fn new(before: &LayerSchema, after: &LayerSchema, tolerance: f64) -> Self {
+        let find = |fields: &[FieldDefinition], name: &str| {
+            fields.iter().find(|f| f.name == name).cloned()
+        };
+        let added_fields = after
+            .fields
+            .fields
+            .iter()
+            .filter(|f| find(&before.fields.fields, &f.name).is_none())
+            .cloned()
+            .collect();
+        let mut removed_fields = vec![];
+        let mut changed_fields = vec![];
+        for f in &before.fields.fields {
+            match find(&after.fields.fields, &f.name) {
+                None => removed_fields.push(f.clone()),
+                Some(g) => {
+                    let (before, after) = (field_definition(f), field_definition(&g));
+                    if before != after {
+                        changed_fields.push(FieldChange {
+                            name: f.name.clone(),
+                            definition: Change { before, after },
+                        });
+                    }
+                }
+            }
+        }
+        Self {
+            name: before.name.clone(),
+            renamed_to: (before.name != after.name).then(|| after.name.clone()),
+            added_fields,
+            removed_fields,
+            changed_fields,
+            srs: (before.srs.wkt != after.srs.wkt).then(|| Change {
+                before: before.srs.clone(),
+                after: after.srs.clone(),
+            }),
+            extent: moved(&before.extent, &after.extent, tolerance).then(|| Change {
+                before: before.extent.clone(),
+                after: after.extent.clone(),
+            }),
+            feature_count: Change {
+                before: before.feature_count,
+                after: after.feature_count,
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.renamed_to.is_none()
+            && self.added_fields.is_empty()
+            && self.removed_fields.is_empty()
+            && self.changed_fields.is_empty()
+            && self.srs.is_none()
+            && self.extent.is_none()
+            && self.feature_count.before == self.feature_count.after
+    }
+}
+
+/// Differences between the layers of two datasets.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    pub before: String,
+    pub after: String,
+    pub added_layers: Vec<String>,
+    pub removed_layers: Vec<String>,
+    /// Layers present in both datasets that differ
+    pub layers: Vec<LayerDiff>,
+}
+
+impl SchemaDiff {
+    /// Compare layers by name. Datasets with a single layer each, such as two shapefiles, have
+    /// that layer compared whatever its name.
+    pub fn new(
+        before: (&str, &[LayerSchema]),
+        after: (&str, &[LayerSchema]),
+        tolerance: f64,
+    ) -> Self {
+        let (a, b) = (before.1, after.1);
+        let mut diff = Self {
+            before: before.0.to_string(),
+            after: after.0.to_string(),
+            ..Default::default()
+        };
+        if let ([a], [b]) = (a, b) {
+            diff.layers = vec![LayerDiff::new(a, b, tolerance)];
+        } else {
+            let find = |layers: &[LayerSchema], name: &str| layers.iter().any(|l| l.name == name);
+            diff.added_layers = b
+                .iter()
+                .filter(|l| !find(a, &l.name))
+                .map(|l| l.name.clone())
+                .collect();
+            diff.removed_layers = a
+                .iter()
+                .filter(|l| !find(b, &l.name))
+                .map(|l| l.name.clone())
+                .collect();
+            diff.layers = a
+                .iter()
+                .filter_map(|l| {
+                    Some(LayerDiff::new(
+                        l,
+                        b.iter().find(|m| m.name == l.name)?,
+                        tolerance,
+                    ))
+                })
+                .collect();
+        }
+        diff.layers.retain(|l| !l.is_empty());
+        diff
+    }
+
+    /// Compare two datasets, only the layers named in `layers` if any.
+    pub fn from_datasets(
+        before: &Dataset,
+        after: &Dataset,
+        layers: &[String],
+        tolerance: f64,
+    ) -> Self {
+        let schemas = |dataset: &Dataset| -> Vec<LayerSchema> {
+            dataset
+                .layers()
+                .filter(|l| layers.is_empty() || layers.contains(&l.name()))
+                .map(|l| LayerSchema::from(&l))
+                .collect()
+        };
+        Self::new(
+            (&before.description().unwrap_or_default(), &schemas(before)),
+            (&after.description().unwrap_or_default(), &schemas(after)),
+            tolerance,
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_layers.is_empty() && self.removed_layers.is_empty() && self.layers.is_empty()
+    }
+}
+
+/// How the features of two layers are paired.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub enum FeatureKey {
+    #[default]
+    Fid,
+    /// Value of this attribute field
+    Field(String),
+}
+
+/// A feature as compared.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct FeatureSnapshot {
+    /// FID or key field value, `None` when null
+    pub key: Option<String>,
+    pub fid: Option<u64>,
+    /// Field names and values formatted by OGR, `None` when null
+    pub values: Vec<(String, Option<String>)>,
+    pub geometry: Option<geo::Geometry>,
+}
+
+impl FeatureSnapshot {
+    /// Read every feature of `layer`, honouring its filters, giving up with `None` as soon as
+    /// `cancel` is cancelled.
+    pub fn read(
+        layer: &mut Layer,
+        key: &FeatureKey,
+        cancel: &CancellationToken,
+    ) -> Result<Option<Vec<Self>>, String> {
+        let names: Vec<String> = layer.defn().fields().map(|f| f.name()).collect();
+        let key_index = match key {
+            FeatureKey::Fid => None,
+            FeatureKey::Field(name) => Some(
+                names
+                    .iter()
+                    .position(|n| n == name)
+                    .ok_or_else(|| format!("No field named '{name}' in {}", layer.name()))?,
+            ),
+        };
+        let mut snapshots = vec![];
+        for f in layer.features() {
+            if cancel.is_cancelled() {
+                return Ok(None);
+            }
+            let values: Vec<(String, Option<String>)> = names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| (name.clone(), f.field_as_string(i as i32).ok().flatten()))
+                .collect();
+            let key = match key_index {
+                Some(i) => values[i].1.clone(),
+                None => f.fid().map(|fid| fid.to_string()),
+            };
+            snapshots.push(Self {
+                key,
+                fid: f.fid(),
+                values,
+                geometry: f.geometry().and_then(|g| g.to_geo().ok()),
+            });
+        }
+        Ok(Some(snapshots))
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeatureChangeKind {
+    Added,
+    Removed,
+    #[default]
+    Modified,
+}
+
+/// An attribute whose value changed.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AttributeChange {
+    pub name: String,
+    pub value: Change<Option<String>>,
+}
+
+/// A feature added, removed or modified between the two layers.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FeatureChange {
+    pub key: String,
+    pub kind: FeatureChangeKind,
+    /// FID in the layer holding the feature, the second one unless removed
+    pub fid: Option<u64>,
+    pub attributes: Vec<AttributeChange>,
+    /// Set when a vertex moved by more than the tolerance
+    pub geometry_changed: bool,
+    /// Geometry as longitude/latitude, for the position map
+    #[serde(skip)]
+    pub geometry: Option<geo::Geometry>,
+}
+
+/// Features that differ between two versions of a layer.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FeatureDiff {
+    pub key: FeatureKey,
+    pub changes: Vec<FeatureChange>,
+    pub unchanged: u64,
+    /// Keys held by several features of a layer, sorted
+    pub duplicate_keys: Vec<String>,
+    /// Features left out of the comparison for lack of a key, in each layer
+    pub null_keys: Change<u64>,
+}
+
+fn geometry_changed(a: &Option<geo::Geometry>, b: &Option<geo::Geometry>, tolerance: f64) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            a != b
+                && (std::mem::discriminant(a) != std::mem::discriminant(b)
+                    || a.hausdorff_distance(b) > tolerance)
+        }
+        (None, None) => false,
+        _ => true,
+    }
+}
+
+impl FeatureDiff {
+    /// Pair the features by key, in the order of the layers when a key is repeated. Features
+    /// without a key are only counted. Only the fields present in both layers are compared.
+    pub fn new(
+        before: Vec<FeatureSnapshot>,
+        after: Vec<FeatureSnapshot>,
+        key: FeatureKey,
+        tolerance: f64,
+    ) -> Self {
+        let group = |features: &[FeatureSnapshot]| {
+            let mut groups: HashMap<String, VecDeque<usize>> = HashMap::new();
+            for (i, f) in features.iter().enumerate() {
+                if let Some(key) = &f.key {
+                    groups.entry(key.clone()).or_default().push_back(i);
+                }
+            }
+            groups
+        };
+        let (before_groups, mut after_groups) = (group(&before), group(&after));
+        let mut duplicate_keys: Vec<String> = before_groups
+            .iter()
+            .chain(&after_groups)
+            .filter(|(_, g)| g.len() > 1)
+            .map(|(k, _)| k.clone())
+            .collect();
+        duplicate_keys.sort();
+        duplicate_keys.dedup();
+        let null_keys = |features: &[FeatureSnapshot]| {
+            features.iter().filter(|f| f.key.is_none()).count() as u64
+        };
+        let mut diff = Self {
+            key,
+            duplicate_keys,
+            null_keys: Change {
+                before: null_keys(&before),
+                after: null_keys(&after),
+            },
+            ..Default::default()
+        };
+        let mut matched = vec![false; after.len()];
+        for a in &before {
+            let Some(key) = &a.key else {
+                continue;
+            };
+            let Some(i) = after_groups.get_mut(key).and_then(|g| g.pop_front()) else {
+                diff.changes.push(FeatureChange {
+                    key: key.clone(),
+                    kind: FeatureChangeKind::Removed,
+                    fid: a.fid,
+                    geometry: a.geometry.clone(),
+                    ..Default::default()
+                });
+                continue;
+            };
+            let b = &after[i];
+            matched[i] = true;
+            let attributes: Vec<AttributeChange> = a
+                .values
+                .iter()
+                .filter_map(|(name, before)| {
+                    let (_, after) = b.values.iter().find(|(n, _)| n == name)?;
+                    (before != after).then(|| AttributeChange {
+                        name: name.clone(),
+                        value: Change {
+                            before: before.clone(),
+                            after: after.clone(),
+                        },
+                    })
+                })
+                .collect();
+            let geometry_changed = geometry_changed(&a.geometry, &b.geometry, tolerance);
+            if attributes.is_empty() && !geometry_changed {
+                diff.unchanged += 1;
+                continue;
+            }
+            diff.changes.push(FeatureChange {
+                key: key.clone(),
+                kind: FeatureChangeKind::Modified,
+                fid: b.fid,
+                attributes,
+                geometry_changed,
+                geometry: b.geometry.clone(),
+            });
+        }
+        for (b, _) in after.iter().zip(matched).filter(|(_, m)| !m) {
+            let Some(key) = &b.key else {
+                continue;
+            };
+            diff.changes.push(FeatureChange {
+                key: key.clone(),
+                kind: FeatureChangeKind::Added,
+                fid: b.fid,
+                geometry: b.geometry.clone(),
+                ..Default::default()
+            });
+        }
+        diff
+    }
+
+    /// Compare two layers, with the geometries of the changes set as longitude/latitude, giving
+    /// up with `None` as soon as `cancel` is cancelled.
+    pub fn from_layers(
+        before: &mut Layer,
+        after: &mut Layer,
+        key: FeatureKey,
+        tolerance: f64,
+        cancel: &CancellationToken,
+    ) -> Result<Option<Self>, String> {
+        let Some(a) = FeatureSnapshot::read(before, &key, cancel)? else {
+            return Ok(None);
+        };
+        let Some(b) = FeatureSnapshot::read(after, &key, cancel)? else {
+            return Ok(None);
+        };
+        let mut diff = Self::new(a, b, key, tolerance);
+        let to_wgs84 = |layer: &Layer| {
+            layer.spatial_ref().and_then(|srs| {
+                CoordTransform::new(&traditional_axis_order(&srs), &wgs84().ok()?).ok()
+            })
+        };
+        let (before, after) = (to_wgs84(before), to_wgs84(after));
+        for change in &mut diff.changes {
+            let transform = match change.kind {
+                FeatureChangeKind::Removed => &before,
+                _ => &after,
+            };
+            // Without a CRS the feature cannot be placed on the map
+            change.geometry = match (&change.geometry, transform) {
+                (Some(g), Some(t)) => reproject_geometry(g, t).ok(),
+                _ => None,
+            };
+        }
+        Ok(Some(diff))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::{line_string, point};
+
+    use super::*;
+
+    fn field(name: &str, type_name: &str, width: i32) -> FieldDefinition {
+        FieldDefinition {
+            name: name.into(),
+            type_name: type_name.into(),
+            width,
+            nullable: true,
+            ..Default::default()
+        }
+    }
+
+    fn schema(name: &str, fields: Vec<FieldDefinition>, feature_count: u64) -> LayerSchema {
+        LayerSchema {
+            name: name.into(),
+            fields: Fields {
+                fields,
+                ..Default::default()
+            },
+            extent: Extent {
+                xmin: 0.,
+                ymin: 0.,
+                xmax: 10.,
+                ymax: 10.,
+            },
+            feature_count,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_layer_diff() {
+        let before = schema(
+            "roads",
+            vec![field("id", "Integer", 0), field("name", "String", 80)],
+            10,
+        );
+        let mut after = schema(
+            "roads",
+            vec![field("name", "String", 120), field("lanes", "Integer", 0)],
+            12,
+        );
+        after.extent.xmax = 10.5;
+        let diff = LayerDiff::new(&before, &after, 0.1);
+        assert_eq!(diff.added_fields, vec![field("lanes", "Integer", 0)]);
+        assert_eq!(diff.removed_fields, vec![field("id", "Integer", 0)]);
+        assert_eq!(
+            diff.changed_fields,
+            vec![FieldChange {
+                name: "name".into(),
+                definition: Change {
+                    before: "String (80.0)".into(),
+                    after: "String (120.0)".into()
+                }
+            }]
+        );
+        assert!(diff.srs.is_none());
+        assert_eq!(diff.extent.map(|e| e.after.xmax), Some(10.5));
+        assert_eq!(
+            diff.feature_count,
+            Change {
+                before: 10,
+                after: 12
+            }
+        );
+        // Within the tolerance
+        assert!(LayerDiff::new(&before, &after, 1.).extent.is_none());
+    }
+
+    #[test]
+    fn test_schema_diff() {
+        let a = [schema("roads", vec![], 1), schema("rivers", vec![], 1)];
+        let b = [schema("roads", vec![], 1), schema("lakes", vec![], 1)];
+        let diff = SchemaDiff::new(("a", &a), ("b", &b), DEFAULT_TOLERANCE);
+        assert_eq!(diff.added_layers, vec!["lakes"]);
+        assert_eq!(diff.removed_layers, vec!["rivers"]);
+        assert!(diff.layers.is_empty());
+        // Lone layers are compared whatever their name
+        let diff = SchemaDiff::new(("a", &a[1..]), ("b", &b[1..]), DEFAULT_TOLERANCE);
+        assert!(diff.added_layers.is_empty());
+        assert_eq!(diff.layers[0].renamed_to.as_deref(), Some("lakes"));
+    }
+
+    #[test]
+    fn test_parse_tolerance() {
+        assert_eq!(parse_tolerance(" "), Ok(DEFAULT_TOLERANCE));
+        assert_eq!(parse_tolerance("0.5"), Ok(0.5));
+        assert_eq!(parse_tolerance("1e-3"), Ok(0.001));
+        assert!(parse_tolerance("-1").is_err());
+        assert!(parse_tolerance("one").is_err());
+    }
+
+    fn feature(key: &str, name: &str, geometry: geo::Geometry) -> FeatureSnapshot {
+        FeatureSnapshot {
+            key: Some(key.into()),
+            fid: key.parse().ok(),
+            values: vec![("name".into(), Some(name.into()))],
+            geometry: Some(geometry),
+        }
+    }
+
+    #[test]
+    fn test_feature_diff() {
+        let line = |dx: f64| line_string![(x: dx, y: 0.), (x: 10., y: 10.)].into();
+        let before = vec![
+            feature("1", "Main street", line(0.)),
+            feature("2", "Station road", line(0.)),
+            feature("3", "Mill lane", point!(x: 1., y: 1.).into()),
+            feature("4", "Church street", line(0.)),
+        ];
+        let after = vec![
+            feature("1", "Main street", line(0.)),
+            feature("2", "Station avenue", line(0.)),
+            feature("4", "Church street", line(0.5)),
+            feature("5", "New road", line(0.)),
+        ];
+        let diff = FeatureDiff::new(before.clone(), after.clone(), FeatureKey::Fid, 0.1);
+        assert_eq!(diff.unchanged, 1);
+        let changes: Vec<(&str, FeatureChangeKind)> = diff
+            .changes
+            .iter()
+            .map(|c| (c.key.as_str(), c.kind))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("2", FeatureChangeKind::Modified),
+                ("3", FeatureChangeKind::Removed),
+                ("4", FeatureChangeKind::Modified),
+                ("5", FeatureChangeKind::Added),
+            ]
+        );
+        assert_eq!(
+            diff.changes[0].attributes,
+            vec![AttributeChange {
+                name: "name".into(),
+                value: Change {
+                    before: Some("Station road".into()),
+                    after: Some("Station avenue".into())
+                }
+            }]
+        );
+        assert!(!diff.changes[0].geometry_changed);
+        assert!(diff.changes[2].attributes.is_empty());
+        assert!(diff.changes[2].geometry_changed);
+        // The moved vertex is within the tolerance
+        let diff = FeatureDiff::new(before, after, FeatureKey::Fid, 1.);
+        assert_eq!(diff.unchanged, 2);
+    }
+
+    #[test]
+    fn test_feature_diff_keys() {
+        let point = || point!(x: 1., y: 1.).into();
+        let null = |name: &str| FeatureSnapshot {
+            key: None,
+            ..feature("0", name, point())
+        };
+        let before = vec![
+            feature("a", "first", point()),
+            feature("a", "second", point()),
+            null("unknown"),
+        ];
+        let after = vec![
+            feature("a", "first", point()),
+            feature("a", "second", point()),
+            feature("a", "third", point()),
+            null("other"),
+        ];
+        let diff = FeatureDiff::new(before, after, FeatureKey::Field("code".into()), 0.);
+        // Repeated keys pair in order, features without a key are left out
+        assert_eq!(diff.unchanged, 2);
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].kind, FeatureChangeKind::Added);
+        assert_eq!(diff.duplicate_keys, vec!["a"]);
+        assert_eq!(
+            diff.null_keys,
+            Change {
+                before: 1,
+                after: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    ffi::{c_char, c_int, c_void, CString},
+    path::PathBuf,
+    ptr,
+};
+
+use gdal::{errors::GdalError, vector::LayerAccess, Dataset, DriverManager, Metadata};
+use serde::{Deserialize, Serialize};
+
+use super::{gdal_message, LayerFilter, DRIVERS};
+
+/// A driver able to write a new vector dataset.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ExportDriver {
+    pub name: String,
+    /// Usual file extension, without the dot
+    pub extension: Option<String>,
+}
+
+/// Registered drivers with vector and create capabilities, in registration order.
+pub fn drivers() -> Vec<ExportDriver> {
+    DRIVERS
+        .iter()
+        // Writes nowhere the user could read it back
+        .filter(|name| *name != "Memory")
+        .filter_map(|name| DriverManager::get_driver_by_name(name).ok())
+        .filter(|d| {
+            d.metadata_item("DCAP_VECTOR", "").is_some()
+                && d.metadata_item("DCAP_CREATE", "").is_some()
+        })
+        .map(|d| ExportDriver {
+            name: d.short_name(),
+            extension: d
+                .metadata_item("DMD_EXTENSION", "")
+                .or_else(|| d.metadata_item("DMD_EXTENSIONS", ""))
+                .and_then(|e| e.split_whitespace().next().map(String::from)),
+        })
+        .collect()
+}
+
+/// Where and how to write a layer.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ExportOptions {
+    pub driver: String,
+    pub path: PathBuf,
+    /// CRS of the output as EPSG code, with or without the `EPSG:` prefix, WKT or PROJ string,
+    /// the layer CRS if `None`
+    pub crs: Option<String>,
+    /// Label the output with `crs` instead of reprojecting to it
+    pub assign_crs: bool,
+}
+
+/// `ogr2ogr` arguments writing the layer `layer_name` with `filter` applied. `geom_field` is the
+/// name of the geometry field the spatial filter applies to.
+fn translate_args(
+    layer_name: &str,
+    geom_field: Option<&str>,
+    filter: &LayerFilter,
+    options: &ExportOptions,
+) -> Vec<String> {
+    let mut args = vec!["-f".to_string(), options.driver.clone()];
+    if let Some(attribute) = &filter.attribute {
+        args.extend(["-where".into(), attribute.clone()]);
+    }
+    if let Some(spatial) = &filter.spatial {
+        let e = &spatial.extent;
+        args.push("-spat".into());
+        args.extend([e.xmin, e.ymin, e.xmax, e.ymax].map(|c| c.to_string()));
+        if let Some(name) = geom_field.filter(|n| !n.is_empty()) {
+            args.extend(["-geomfield".into(), name.to_string()]);
+        }
+    }
+    if let Some(crs) = &options.crs {
+        let flag = if options.assign_crs {
+            "-a_srs"
+        } else {
+            "-t_srs"
+        };
+        let crs = match crs.parse::<u32>() {
+            Ok(code) => format!("EPSG:{code}"),
+            Err(_) => crs.clone(),
+        };
+        args.extend([flag.into(), crs]);
+    }
+    args.push(layer_name.to_string());
+    args
+}
+
+unsafe extern "C" fn progress_callback<F: FnMut(f64)>(
+    complete: f64,
+    _message: *const c_char,
+    data: *mut c_void,
+) -> c_int {
+    let progress = &mut *(data as *mut F);
+    progress(complete);
+    1
+}
+
+/// Write the layer `layer` of `dataset` to a new dataset, as `ogr2ogr` would. `progress` is
+/// called with the completed fraction, from 0 to 1.
+pub fn export<F: FnMut(f64)>(
+    dataset: &Dataset,
+    layer: usize,
+    filter: &LayerFilter,
+    options: &ExportOptions,
+    mut progress: F,
+) -> Result<(), String> {
+    gdal_message(|| {
+        let l = dataset.layer(layer as isize)?;
+        let geom_field = filter
+            .spatial
+            .as_ref()
+            .and_then(|s| l.defn().geom_fields().nth(s.geom_field))
+            .map(|g| g.name());
+        let args = translate_args(&l.name(), geom_field.as_deref(), filter, options)
+            .into_iter()
+            .map(CString::new)
+            .collect::<Result<Vec<_>, _>>()?;
+        // The C API is not const-correct, the strings are not modified
+        let mut c_args: Vec<*mut c_char> = args
+            .iter()
+            .map(|a| a.as_ptr() as *mut c_char)
+            .chain(std::iter::once(ptr::null_mut()))
+            .collect();
+        let dest = CString::new(options.path.to_string_lossy().as_bytes())?;
+
+        unsafe {
+            let c_options =
+                gdal_sys::GDALVectorTranslateOptionsNew(c_args.as_mut_ptr(), ptr::null_mut());
+            if c_options.is_null() {
+                return Err(GdalError::NullPointer {
+                    method_name: "GDALVectorTranslateOptionsNew",
+                    msg: String::new(),
+                });
+            }
+            gdal_sys::GDALVectorTranslateOptionsSetProgress(
+                c_options,
+                Some(progress_callback::<F>),
+                &mut progress as *mut F as *mut c_void,
+            );
+            let mut source = dataset.c_dataset();
+            let c_dataset = gdal_sys::GDALVectorTranslate(
+                dest.as_ptr(),
+                ptr::null_mut(),
+                1,
+                &mut source,
+                c_options,
+                ptr::null_mut(),
+            );
+            gdal_sys::GDALVectorTranslateOptionsFree(c_options);
+            if c_dataset.is_null() {
+                return Err(GdalError::NullPointer {
+                    method_name: "GDALVectorTranslate",
+                    msg: String::new(),
+                });
+            }
+            // Closing the dataset flushes it to disk
+            gdal_sys::GDALClose(c_dataset);
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{extent::Extent, SpatialFilter};
+
+    #[test]
+    fn test_translate_args() {
+        let options = ExportOptions {
+            driver: "GPKG".into(),
+            path: "roads.gpkg".into(),
+            crs: Some("2056".into()),
+            assign_crs: false,
+        };
+        assert_eq!(
+            translate_args("roads", None, &LayerFilter::default(), &options),
+            vec!["-f", "GPKG", "-t_srs", "EPSG:2056", "roads"]
+        );
+        let assign = ExportOptions {
+            crs: Some("ESRI:102100".into()),
+            assign_crs: true,
+            ..options.clone()
+        };
+        assert_eq!(
+            translate_args("roads", None, &LayerFilter::default(), &assign),
+            vec!["-f", "GPKG", "-a_srs", "ESRI:102100", "roads"]
+        );
+        let filter = LayerFilter {
+            attribute: Some("type = 'primary'".into()),
+            spatial: Some(SpatialFilter {
+                geom_field: 0,
+                extent: Extent {
+                    xmin: 6.,
+                    ymin: 46.,
+                    xmax: 7.5,
+                    ymax: 47.,
+                },
+            }),
+        };
+        let options = ExportOptions {
+            crs: None,
+            ..options
+        };
+        assert_eq!(
+            translate_args("roads", Some("geom"), &filter, &options),
+            vec![
+                "-f",
+                "GPKG",
+                "-where",
+                "type = 'primary'",
+                "-spat",
+                "6",
+                "46",
+                "7.5",
+                "47",
+                "-geomfield",
+                "geom",
+                "roads"
+            ]
+        );
+    }
+}
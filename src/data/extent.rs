@@ -1,9 +1,15 @@
 use gdal::{
-    spatial_ref::SpatialRef,
+    errors::GdalError,
+    spatial_ref::{CoordTransform, SpatialRef},
     vector::{Geometry, Layer, LayerAccess},
+    Dataset,
 };
+use gdal_sys::OGRErr;
+
+use super::srs::{traditional_axis_order, wgs84};
 use geo::{BoundingRect, GeometryCollection};
 use serde::{Deserialize, Serialize};
+use std::{ffi::c_int, mem::MaybeUninit};
 
 #[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Extent {
@@ -14,20 +20,122 @@ pub struct Extent {
 }
 
 impl Extent {
-    pub fn reproject(self, ssr: SpatialRef, dsr: SpatialRef) -> Self {
-        let mut new_extent = Geometry::bbox(self.xmin, self.ymin, self.xmax, self.ymax).unwrap();
-        new_extent.set_spatial_ref(ssr);
-        new_extent.transform_to_inplace(&dsr).unwrap();
-        let new_envelope = new_extent.envelope();
+    /// Bounding box of this extent, expressed in `ssr`, once transformed to `dsr`. Both are
+    /// taken easting or longitude first, so that x stays x whatever their authority says. The
+    /// edges are densified, since they bend between the corners in most projections.
+    pub fn reproject(&self, ssr: &SpatialRef, dsr: &SpatialRef) -> Result<Self, GdalError> {
+        let transform =
+            CoordTransform::new(&traditional_axis_order(ssr), &traditional_axis_order(dsr))?;
+        let [xmin, ymin, xmax, ymax] =
+            transform.transform_bounds(&[self.xmin, self.ymin, self.xmax, self.ymax], 21)?;
+        Ok(Self {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+        })
+    }
+
+    /// Extent spanning two corners given in any order.
+    pub fn from_corners(a: (f64, f64), b: (f64, f64)) -> Self {
+        Self {
+            xmin: a.0.min(b.0),
+            ymin: a.1.min(b.1),
+            xmax: a.0.max(b.0),
+            ymax: a.1.max(b.1),
+        }
+    }
+
+    /// Smallest extent containing both.
+    pub fn union(&self, other: &Self) -> Self {
         Self {
-            xmin: new_envelope.MinX,
-            ymin: new_envelope.MinY,
-            xmax: new_envelope.MaxX,
-            ymax: new_envelope.MaxY,
+            xmin: self.xmin.min(other.xmin),
+            ymin: self.ymin.min(other.ymin),
+            xmax: self.xmax.max(other.xmax),
+            ymax: self.ymax.max(other.ymax),
+        }
+    }
+
+    /// Bounding box of a raster of `size` pixels placed by the geotransform `gt`.
+    pub fn from_geo_transform(gt: &[f64; 6], size: (usize, usize)) -> Self {
+        let (width, height) = (size.0 as f64, size.1 as f64);
+        let corners = [(0., 0.), (width, 0.), (0., height), (width, height)].map(|(px, py)| {
+            (
+                gt[0] + px * gt[1] + py * gt[2],
+                gt[3] + px * gt[4] + py * gt[5],
+            )
+        });
+        Self {
+            xmin: corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min),
+            ymin: corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min),
+            xmax: corners
+                .iter()
+                .map(|c| c.0)
+                .fold(f64::NEG_INFINITY, f64::max),
+            ymax: corners
+                .iter()
+                .map(|c| c.1)
+                .fold(f64::NEG_INFINITY, f64::max),
         }
     }
 }
 
+impl Extent {
+    /// Extent of the geometry field `index` of `layer`, `None` if GDAL can't compute it, as
+    /// for a layer without geometries.
+    pub fn from_geom_field(layer: &Layer, index: usize) -> Option<Self> {
+        let mut envelope = MaybeUninit::uninit();
+        let err = unsafe {
+            gdal_sys::OGR_L_GetExtentEx(layer.c_layer(), index as c_int, envelope.as_mut_ptr(), 1)
+        };
+        if err != OGRErr::OGRERR_NONE {
+            return None;
+        }
+        let extent = unsafe { envelope.assume_init() };
+        Some(Self {
+            xmin: extent.MinX,
+            ymin: extent.MinY,
+            xmax: extent.MaxX,
+            ymax: extent.MaxY,
+        })
+    }
+
+    /// Bounding box of `geometry`, `None` if it is empty.
+    pub fn from_geometry(geometry: &Geometry) -> Option<Self> {
+        if geometry.is_empty() {
+            return None;
+        }
+        let envelope = geometry.envelope();
+        Some(Self {
+            xmin: envelope.MinX,
+            ymin: envelope.MinY,
+            xmax: envelope.MaxX,
+            ymax: envelope.MaxY,
+        })
+    }
+}
+
+impl Extent {
+    /// Extent of every layer and of the raster of `dataset` as longitude/latitude, leaving out
+    /// those that are empty or have no CRS.
+    pub fn of_dataset(dataset: &Dataset) -> Option<Self> {
+        let dsr = wgs84().ok()?;
+        let mut extents: Vec<Self> = dataset
+            .layers()
+            .filter_map(|layer| {
+                let e = layer.get_extent().ok()?;
+                let extent = Self::from_corners((e.MinX, e.MinY), (e.MaxX, e.MaxY));
+                extent.reproject(&layer.spatial_ref()?, &dsr).ok()
+            })
+            .collect();
+        if let (Some(srs), Ok(gt)) = (dataset.spatial_ref().ok(), dataset.geo_transform()) {
+            let extent = Self::from_geo_transform(&gt, dataset.raster_size());
+            extents.extend(extent.reproject(&srs, &dsr).ok());
+        }
+        extents.into_iter().reduce(|a, b| a.union(&b))
+    }
+}
+
 impl From<&Layer<'_>> for Extent {
     fn from(layer: &Layer) -> Self {
         if let Ok(extent) = layer.get_extent() {
@@ -59,3 +167,127 @@ impl From<Vec<geo::Geometry>> for Extent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// Extent of `layer` in the test dataset, as longitude/latitude.
+    fn wgs84_extent(layer: &str) -> Extent {
+        let dataset = Dataset::open("data/my_dataset.gpkg").unwrap();
+        let layer = dataset.layer_by_name(layer).unwrap();
+        let srs = layer.spatial_ref().unwrap();
+        Extent::from(&layer)
+            .reproject(&srs, &wgs84().unwrap())
+            .unwrap()
+    }
+
+    fn assert_within(extent: &Extent, lon: (f64, f64), lat: (f64, f64)) {
+        assert!(lon.0 <= extent.xmin && extent.xmax <= lon.1, "{extent:?}");
+        assert!(lat.0 <= extent.ymin && extent.ymax <= lat.1, "{extent:?}");
+    }
+
+    #[test]
+    fn test_union() {
+        let a = Extent::from_corners((0., 0.), (2., 1.));
+        let b = Extent::from_corners((-1., 0.5), (1., 3.));
+        assert_eq!(a.union(&b), Extent::from_corners((-1., 0.), (2., 3.)));
+    }
+
+    #[test]
+    fn test_of_dataset() {
+        // Layers in LV95 and WGS 84 from Texas to Manchuria
+        let dataset = Dataset::open("data/my_dataset.gpkg").unwrap();
+        let extent = Extent::of_dataset(&dataset).unwrap();
+        for layer in ["ms_envconst_dioxines", "us_data", "asia_data"] {
+            assert_eq!(extent.union(&wgs84_extent(layer)), extent);
+        }
+    }
+
+    #[test]
+    fn test_reproject_projected() {
+        // LV95 points around Lausanne
+        assert_within(
+            &wgs84_extent("ms_envconst_dioxines"),
+            (6.5, 6.8),
+            (46.4, 46.7),
+        );
+    }
+
+    #[test]
+    fn test_reproject_geographic() {
+        assert_within(&wgs84_extent("us_data"), (-103., -90.), (26., 44.));
+        assert_within(&wgs84_extent("asia_data"), (108., 125.), (27., 57.));
+    }
+
+    #[test]
+    fn test_reproject_authority_order() {
+        // EPSG:4326 as GDAL 3 builds it puts latitude first, longitude is still read as x
+        let extent = Extent {
+            xmin: 6.5,
+            ymin: 46.5,
+            xmax: 6.7,
+            ymax: 46.6,
+        };
+        let lv95 = extent
+            .reproject(
+                &SpatialRef::from_epsg(4326).unwrap(),
+                &SpatialRef::from_epsg(2056).unwrap(),
+            )
+            .unwrap();
+        assert_within(&lv95, (2_520_000., 2_555_000.), (1_145_000., 1_165_000.));
+    }
+
+    #[test]
+    fn test_reproject_densified() {
+        // Parallels bend poleward away from the central meridian in UTM, so the top edge of a
+        // box reaches further north halfway than at its corners
+        let utm = SpatialRef::from_epsg(32632).unwrap();
+        let extent = Extent::from_corners((300_000., 5_000_000.), (700_000., 5_500_000.));
+        let reprojected = extent.reproject(&utm, &wgs84().unwrap()).unwrap();
+        let transform =
+            CoordTransform::new(&traditional_axis_order(&utm), &wgs84().unwrap()).unwrap();
+        let mut x = [300_000., 700_000., 300_000., 700_000.];
+        let mut y = [5_000_000., 5_000_000., 5_500_000., 5_500_000.];
+        transform
+            .transform_coords(&mut x, &mut y, &mut [0.; 4])
+            .unwrap();
+        let corners_ymax = y.into_iter().fold(f64::NEG_INFINITY, f64::max);
+        assert!(
+            reprojected.ymax > corners_ymax + 0.01,
+            "{reprojected:?} {corners_ymax}"
+        );
+    }
+
+    #[test]
+    fn test_from_geo_transform_north_up() {
+        let gt = [2500000., 10., 0., 1300000., 0., -10.];
+        let extent = Extent::from_geo_transform(&gt, (100, 50));
+        assert_eq!(
+            extent,
+            Extent {
+                xmin: 2500000.,
+                ymin: 1299500.,
+                xmax: 2501000.,
+                ymax: 1300000.,
+            }
+        );
+    }
+
+    #[test]
+    fn test_from_geo_transform_rotated() {
+        let gt = [0., 1., 1., 0., 1., -1.];
+        let extent = Extent::from_geo_transform(&gt, (2, 2));
+        assert_eq!(
+            extent,
+            Extent {
+                xmin: 0.,
+                ymin: -2.,
+                xmax: 4.,
+                ymax: 2.,
+            }
+        );
+    }
+}
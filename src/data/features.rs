@@ -0,0 +1,194 @@
+use gdal::{
+    errors::GdalError,
+    vector::{field_type_to_name, Feature, Geometry, Layer, LayerAccess},
+};
+use gdal_sys::OGRErr;
+use serde::{Deserialize, Serialize};
+
+/// Number of features read at once by the attribute table.
+pub const PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FeatureRow {
+    pub fid: Option<u64>,
+    /// Field values formatted by OGR, `None` when null
+    pub values: Vec<Option<String>>,
+}
+
+impl From<&Feature<'_>> for FeatureRow {
+    fn from(feature: &Feature<'_>) -> Self {
+        Self {
+            fid: feature.fid(),
+            values: (0..feature.field_count())
+                .map(|i| feature.field_as_string(i).ok().flatten())
+                .collect(),
+        }
+    }
+}
+
+/// A window of consecutive features of a layer.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FeaturePage {
+    /// Index of the first row among the features of the layer
+    pub offset: u64,
+    pub columns: Vec<String>,
+    pub rows: Vec<FeatureRow>,
+}
+
+impl FeaturePage {
+    /// Read up to `PAGE_SIZE` features of `layer` starting from the `offset`-th one, honouring
+    /// the layer filters.
+    pub fn read(layer: &mut Layer, offset: u64) -> Result<Self, GdalError> {
+        let columns = layer.defn().fields().map(|f| f.name()).collect();
+        let c_layer = unsafe { layer.c_layer() };
+        let features = layer.features();
+        // Skip to the page without reading the features before it, where the driver allows
+        let err = unsafe { gdal_sys::OGR_L_SetNextByIndex(c_layer, offset as i64) };
+        if err != OGRErr::OGRERR_NONE {
+            return Err(GdalError::OgrError {
+                err,
+                method_name: "OGR_L_SetNextByIndex",
+            });
+        }
+        let rows = features
+            .take(PAGE_SIZE)
+            .map(|f| FeatureRow::from(&f))
+            .collect();
+        Ok(Self {
+            offset,
+            columns,
+            rows,
+        })
+    }
+
+    /// Index of the feature `fid` among the features of `layer`, honouring the layer filters.
+    pub fn feature_index(layer: &mut Layer, fid: u64) -> Option<u64> {
+        layer
+            .features()
+            .position(|f| f.fid() == Some(fid))
+            .map(|i| i as u64)
+    }
+}
+
+/// Geometries are written out as text up to this many characters.
+const MAX_TEXT_LEN: usize = 4096;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum FieldState {
+    Unset,
+    Null,
+    Value(String),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FieldInfo {
+    pub name: String,
+    pub field_type: String,
+    pub value: FieldState,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct GeometryInfo {
+    pub name: String,
+    pub geometry_type: String,
+    pub dimensions: String,
+    pub vertex_count: usize,
+    pub wkt: String,
+    pub geojson: String,
+}
+
+impl GeometryInfo {
+    fn new(name: String, geometry: &Geometry) -> Self {
+        let (is_3d, is_measured) = unsafe {
+            (
+                gdal_sys::OGR_G_Is3D(geometry.c_geometry()) != 0,
+                gdal_sys::OGR_G_IsMeasured(geometry.c_geometry()) != 0,
+            )
+        };
+        let dimensions = match (is_3d, is_measured) {
+            (false, false) => "XY",
+            (true, false) => "XYZ",
+            (false, true) => "XYM",
+            (true, true) => "XYZM",
+        };
+        Self {
+            name,
+            geometry_type: geometry.geometry_name(),
+            dimensions: dimensions.into(),
+            vertex_count: vertex_count(geometry),
+            wkt: truncate(geometry.wkt().unwrap_or_default()),
+            geojson: truncate(geometry.json().unwrap_or_default()),
+        }
+    }
+}
+
+fn vertex_count(geometry: &Geometry) -> usize {
+    geometry.point_count()
+        + (0..geometry.geometry_count())
+            .map(|i| vertex_count(&geometry.get_geometry(i)))
+            .sum::<usize>()
+}
+
+fn truncate(mut text: String) -> String {
+    if let Some((i, _)) = text.char_indices().nth(MAX_TEXT_LEN) {
+        text.truncate(i);
+        text.push('…');
+    }
+    text
+}
+
+/// Everything about a single feature, for the feature panel.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FeatureInfo {
+    pub fid: Option<u64>,
+    pub fields: Vec<FieldInfo>,
+    pub geometries: Vec<GeometryInfo>,
+}
+
+impl FeatureInfo {
+    pub fn new(layer: &Layer, feature: &Feature<'_>) -> Self {
+        let c_feature = unsafe { feature.c_feature() };
+        let fields = layer
+            .defn()
+            .fields()
+            .enumerate()
+            .map(|(i, f)| {
+                let i = i as i32;
+                let value = if unsafe { gdal_sys::OGR_F_IsFieldSet(c_feature, i) } == 0 {
+                    FieldState::Unset
+                } else if unsafe { gdal_sys::OGR_F_IsFieldNull(c_feature, i) } != 0 {
+                    FieldState::Null
+                } else {
+                    FieldState::Value(
+                        feature
+                            .field_as_string(i)
+                            .ok()
+                            .flatten()
+                            .unwrap_or_default(),
+                    )
+                };
+                FieldInfo {
+                    name: f.name(),
+                    field_type: field_type_to_name(f.field_type()),
+                    value,
+                }
+            })
+            .collect();
+        let geometries = layer
+            .defn()
+            .geom_fields()
+            .enumerate()
+            .filter_map(|(i, g)| {
+                feature
+                    .geometry_by_index(i)
+                    .ok()
+                    .map(|geometry| GeometryInfo::new(g.name(), geometry))
+            })
+            .collect();
+        Self {
+            fid: feature.fid(),
+            fields,
+            geometries,
+        }
+    }
+}
@@ -1,20 +1,392 @@
-use gdal::vector::{Layer, LayerAccess};
+use std::{
+    collections::HashSet,
+    ffi::{c_char, c_int, CStr},
+};
+
+use gdal::vector::{
+    field_type_to_name, geometry_type_to_name, Feature, FieldValue, Layer, LayerAccess,
+};
+use gdal_sys::OGRFieldSubType;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Eq, PartialEq, Clone, Serialize, Deserialize)]
+/// Distinct values are no longer counted past this number.
+pub const MAX_DISTINCT: usize = 10_000;
+
+/// Definition of an attribute field.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FieldDefinition {
+    pub name: String,
+    pub type_name: String,
+    /// Subtype refining the type, such as boolean, int16, json or uuid
+    pub subtype: Option<String>,
+    pub width: i32,
+    pub precision: i32,
+    pub nullable: bool,
+    pub unique: bool,
+    pub default: Option<String>,
+    pub alternative_name: Option<String>,
+    pub comment: Option<String>,
+    pub domain: Option<String>,
+}
+
+/// Definition of a geometry field.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct GeomFieldDefinition {
+    pub name: String,
+    pub geometry_type: String,
+    /// Name of the CRS of the field, if any
+    pub srs: Option<String>,
+    pub nullable: bool,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Fields {
-    pub geom_field: Vec<String>,
-    pub fields: Vec<(String, u32)>,
+    pub geom_fields: Vec<GeomFieldDefinition>,
+    pub fields: Vec<FieldDefinition>,
+    /// Statistics of each field, in the order of `fields`
+    pub stats: Vec<FieldStats>,
+}
+
+/// A string owned by GDAL, `None` when null or empty.
+fn c_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let s = unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned();
+    (!s.is_empty()).then_some(s)
+}
+
+/// Comment of a field, which GDAL only stores since 3.7.
+#[cfg(gdal_field_comment)]
+fn field_comment(c_field: gdal_sys::OGRFieldDefnH) -> Option<String> {
+    c_string(unsafe { gdal_sys::OGR_Fld_GetComment(c_field) })
+}
+
+#[cfg(not(gdal_field_comment))]
+fn field_comment(_: gdal_sys::OGRFieldDefnH) -> Option<String> {
+    None
 }
 
 impl From<&Layer<'_>> for Fields {
     fn from(layer: &Layer<'_>) -> Self {
-        let fields: Vec<(String, u32)> = layer
+        let c_defn = unsafe { gdal_sys::OGR_L_GetLayerDefn(layer.c_layer()) };
+        let fields = layer
             .defn()
             .fields()
-            .map(|f| (f.name(), f.field_type()))
+            .enumerate()
+            .map(|(i, f)| {
+                let c_field = unsafe { gdal_sys::OGR_FD_GetFieldDefn(c_defn, i as c_int) };
+                let subtype = unsafe { gdal_sys::OGR_Fld_GetSubType(c_field) };
+                FieldDefinition {
+                    name: f.name(),
+                    type_name: field_type_to_name(f.field_type()),
+                    subtype: (subtype != OGRFieldSubType::OFSTNone).then(|| {
+                        c_string(unsafe { gdal_sys::OGR_GetFieldSubTypeName(subtype) })
+                            .unwrap_or_default()
+                    }),
+                    width: f.width(),
+                    precision: f.precision(),
+                    nullable: unsafe { gdal_sys::OGR_Fld_IsNullable(c_field) } != 0,
+                    unique: unsafe { gdal_sys::OGR_Fld_IsUnique(c_field) } != 0,
+                    default: c_string(unsafe { gdal_sys::OGR_Fld_GetDefault(c_field) }),
+                    alternative_name: c_string(unsafe {
+                        gdal_sys::OGR_Fld_GetAlternativeNameRef(c_field)
+                    }),
+                    comment: field_comment(c_field),
+                    domain: c_string(unsafe { gdal_sys::OGR_Fld_GetDomainName(c_field) }),
+                }
+            })
+            .collect();
+        let geom_fields = layer
+            .defn()
+            .geom_fields()
+            .enumerate()
+            .map(|(i, g)| {
+                let c_field = unsafe { gdal_sys::OGR_FD_GetGeomFieldDefn(c_defn, i as c_int) };
+                GeomFieldDefinition {
+                    name: g.name(),
+                    geometry_type: geometry_type_to_name(g.field_type()),
+                    srs: g.spatial_ref().ok().and_then(|s| s.name().ok()),
+                    nullable: unsafe { gdal_sys::OGR_GFld_IsNullable(c_field) } != 0,
+                }
+            })
+            .collect();
+        Self {
+            geom_fields,
+            fields,
+            stats: vec![],
+        }
+    }
+}
+
+/// Statistics of the values of a field over all the features of a layer.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FieldStats {
+    pub null: u64,
+    /// Number of distinct values, `None` past `MAX_DISTINCT`
+    pub distinct: Option<u64>,
+    /// Smallest numeric value or earliest date
+    pub min: Option<String>,
+    /// Largest numeric value or latest date
+    pub max: Option<String>,
+    pub mean: Option<f64>,
+    /// Shortest and longest string
+    pub length: Option<(usize, usize)>,
+}
+
+/// Accumulates the values of one field into `FieldStats`.
+#[derive(Debug, Default)]
+struct FieldStatsBuilder {
+    null: u64,
+    distinct: Option<HashSet<String>>,
+    /// Sum and count of the numeric values
+    numbers: Option<(f64, u64)>,
+    /// Bounds of the integer values, kept exact past 2^53
+    integers: Option<(i64, i64)>,
+    reals: Option<(f64, f64)>,
+    dates: Option<(FieldValue, FieldValue)>,
+    length: Option<(usize, usize)>,
+}
+
+impl FieldStatsBuilder {
+    fn add(&mut self, value: Option<FieldValue>) {
+        let Some(value) = value else {
+            self.null += 1;
+            return;
+        };
+        if let Some(distinct) = &mut self.distinct {
+            distinct.insert(format!("{value:?}"));
+            if distinct.len() > MAX_DISTINCT {
+                self.distinct = None;
+            }
+        }
+        let number = match value {
+            FieldValue::IntegerValue(n) => Some(n as f64),
+            FieldValue::Integer64Value(n) => Some(n as f64),
+            FieldValue::RealValue(n) => Some(n),
+            _ => None,
+        };
+        if let Some(n) = number {
+            let (sum, count) = self.numbers.get_or_insert((0., 0));
+            *sum += n;
+            *count += 1;
+            match value {
+                FieldValue::IntegerValue(i) => self.add_integer(i.into()),
+                FieldValue::Integer64Value(i) => self.add_integer(i),
+                _ => {
+                    let (min, max) = self.reals.get_or_insert((n, n));
+                    *min = min.min(n);
+                    *max = max.max(n);
+                }
+            }
+        } else if let FieldValue::StringValue(s) = &value {
+            let len = s.chars().count();
+            let (min, max) = self.length.get_or_insert((len, len));
+            *min = (*min).min(len);
+            *max = (*max).max(len);
+        } else if matches!(
+            value,
+            FieldValue::DateValue(_) | FieldValue::DateTimeValue(_)
+        ) {
+            match &mut self.dates {
+                None => self.dates = Some((value.clone(), value)),
+                Some((min, max)) => {
+                    if is_before(&value, min) {
+                        *min = value;
+                    } else if is_before(max, &value) {
+                        *max = value;
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_integer(&mut self, n: i64) {
+        let (min, max) = self.integers.get_or_insert((n, n));
+        *min = (*min).min(n);
+        *max = (*max).max(n);
+    }
+
+    fn build(self) -> FieldStats {
+        let date = |v: FieldValue| match v {
+            FieldValue::DateValue(d) => d.to_string(),
+            FieldValue::DateTimeValue(d) => d.to_string(),
+            _ => String::new(),
+        };
+        let (min, max) = match (self.integers, self.reals, self.dates) {
+            (Some((min, max)), None, _) => (Some(min.to_string()), Some(max.to_string())),
+            (None, Some((min, max)), _) => (Some(min.to_string()), Some(max.to_string())),
+            // A field holds one type, so mixing both only happens with odd drivers
+            (Some((imin, imax)), Some((rmin, rmax)), _) => (
+                Some(if (imin as f64) < rmin {
+                    imin.to_string()
+                } else {
+                    rmin.to_string()
+                }),
+                Some(if (imax as f64) > rmax {
+                    imax.to_string()
+                } else {
+                    rmax.to_string()
+                }),
+            ),
+            (None, None, Some((min, max))) => (Some(date(min)), Some(date(max))),
+            (None, None, None) => (None, None),
+        };
+        FieldStats {
+            null: self.null,
+            distinct: self.distinct.map(|d| d.len() as u64),
+            min,
+            max,
+            mean: self.numbers.map(|(sum, count)| sum / count as f64),
+            length: self.length,
+        }
+    }
+}
+
+fn is_before(a: &FieldValue, b: &FieldValue) -> bool {
+    match (a, b) {
+        (FieldValue::DateValue(a), FieldValue::DateValue(b)) => a < b,
+        (FieldValue::DateTimeValue(a), FieldValue::DateTimeValue(b)) => a < b,
+        // A date alone stands for its midnight
+        (FieldValue::DateValue(a), FieldValue::DateTimeValue(b)) => {
+            a.and_hms_opt(0, 0, 0).is_some_and(|a| a < b.naive_local())
+        }
+        (FieldValue::DateTimeValue(a), FieldValue::DateValue(b)) => {
+            b.and_hms_opt(0, 0, 0).is_some_and(|b| a.naive_local() < b)
+        }
+        _ => false,
+    }
+}
+
+/// Computes the `FieldStats` of every field of a layer, one feature at a time.
+#[derive(Debug, Default)]
+pub struct FieldStatsCollector {
+    names: Vec<String>,
+    builders: Vec<FieldStatsBuilder>,
+}
+
+impl FieldStatsCollector {
+    pub fn new(layer: &Layer) -> Self {
+        let names: Vec<String> = layer.defn().fields().map(|f| f.name()).collect();
+        let builders = names
+            .iter()
+            .map(|_| FieldStatsBuilder {
+                distinct: Some(HashSet::new()),
+                ..Default::default()
+            })
             .collect();
-        let geom_field: Vec<String> = layer.defn().geom_fields().map(|g| g.name()).collect();
-        Self { geom_field, fields }
+        Self { names, builders }
+    }
+
+    pub fn add(&mut self, feature: &Feature) {
+        for (name, builder) in self.names.iter().zip(&mut self.builders) {
+            // Fields of a type GDAL does not convert, such as binary, are left out
+            if let Ok(value) = feature.field(name) {
+                builder.add(value);
+            }
+        }
+    }
+
+    pub fn build(self) -> Vec<FieldStats> {
+        self.builders.into_iter().map(|b| b.build()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn stats(values: Vec<Option<FieldValue>>) -> FieldStats {
+        let mut builder = FieldStatsBuilder {
+            distinct: Some(HashSet::new()),
+            ..Default::default()
+        };
+        for value in values {
+            builder.add(value);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_numeric_stats() {
+        let stats = stats(vec![
+            Some(FieldValue::RealValue(2.5)),
+            None,
+            Some(FieldValue::RealValue(-1.)),
+            Some(FieldValue::RealValue(2.5)),
+            None,
+            Some(FieldValue::RealValue(4.)),
+        ]);
+        assert_eq!(
+            stats,
+            FieldStats {
+                null: 2,
+                distinct: Some(3),
+                min: Some("-1".into()),
+                max: Some("4".into()),
+                mean: Some(2.),
+                length: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_integer64_bounds() {
+        // Both round to the same f64
+        let big = i64::MAX - 1;
+        let stats = stats(vec![
+            Some(FieldValue::Integer64Value(big)),
+            Some(FieldValue::Integer64Value(i64::MAX)),
+            Some(FieldValue::Integer64Value(big)),
+        ]);
+        assert_eq!(stats.min, Some(big.to_string()));
+        assert_eq!(stats.max, Some(i64::MAX.to_string()));
+    }
+
+    #[test]
+    fn test_string_lengths() {
+        let stats = stats(vec![
+            Some(FieldValue::StringValue("Lausanne".into())),
+            Some(FieldValue::StringValue("Zürich".into())),
+            None,
+            Some(FieldValue::StringValue("".into())),
+        ]);
+        assert_eq!(stats.null, 1);
+        assert_eq!(stats.distinct, Some(3));
+        assert_eq!(stats.length, Some((0, 8)));
+        assert_eq!((stats.min, stats.max, stats.mean), (None, None, None));
+    }
+
+    #[test]
+    fn test_distinct_cutoff() {
+        let values = |n: usize| (0..n as i32).map(|i| Some(FieldValue::IntegerValue(i)));
+        assert_eq!(
+            stats(values(MAX_DISTINCT).collect()).distinct,
+            Some(MAX_DISTINCT as u64)
+        );
+        let stats = stats(values(MAX_DISTINCT + 1).collect());
+        assert_eq!(stats.distinct, None);
+        assert_eq!(stats.max, Some(MAX_DISTINCT.to_string()));
+    }
+
+    #[test]
+    fn test_mixed_date_range() {
+        let stats = stats(vec![
+            Some(FieldValue::DateTimeValue(
+                "2021-06-01T12:00:00+00:00".parse().unwrap(),
+            )),
+            Some(FieldValue::DateValue("2020-01-01".parse().unwrap())),
+            Some(FieldValue::DateTimeValue(
+                "2021-06-02T08:30:00+00:00".parse().unwrap(),
+            )),
+            Some(FieldValue::DateValue("2021-06-01".parse().unwrap())),
+            Some(FieldValue::DateValue("2022-03-15".parse().unwrap())),
+        ]);
+        assert_eq!(stats.min, Some("2020-01-01".into()));
+        assert_eq!(stats.max, Some("2022-03-15".into()));
     }
 }
@@ -0,0 +1,299 @@
+use std::{collections::BTreeMap, path::Path};
+
+use gdal::{
+    errors::GdalError,
+    spatial_ref::CoordTransform,
+    vector::{geometry_type_to_name, Feature, Geometry, Layer, LayerAccess},
+};
+use gdal_sys::OGRwkbGeometryType;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{
+    srs::{traditional_axis_order, wgs84},
+    validity::{validate, Invalidity},
+};
+
+/// Problem features listed beyond this number are only counted.
+pub const MAX_PROBLEMS: usize = 1000;
+
+/// Geometries of a layer drawn beyond this number are left out, to keep the map responsive.
+pub const MAX_DRAWN_GEOMETRIES: usize = 10_000;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum GeometryProblem {
+    Null,
+    Empty,
+    /// Geometry `geo` has no equivalent for, such as curves
+    Unconvertible(String),
+    Invalid(Invalidity),
+}
+
+impl std::fmt::Display for GeometryProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeometryProblem::Null => write!(f, "null"),
+            GeometryProblem::Empty => write!(f, "empty"),
+            GeometryProblem::Unconvertible(t) => write!(f, "unconvertible {t}"),
+            GeometryProblem::Invalid(i) => write!(f, "{} at ({}, {})", i.reason, i.x, i.y),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ProblemFeature {
+    pub fid: Option<u64>,
+    pub problem: GeometryProblem,
+}
+
+/// Geometry types and problems found while reading the features of a layer.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct GeometrySummary {
+    /// Number of geometries per type name
+    pub types: BTreeMap<String, u64>,
+    pub null: u64,
+    pub empty: u64,
+    pub unconvertible: u64,
+    pub invalid: u64,
+    /// First `MAX_PROBLEMS` problem features
+    pub problems: Vec<ProblemFeature>,
+}
+
+impl GeometrySummary {
+    /// Record the first geometry of `feature`.
+    pub fn add(&mut self, feature: &Feature) {
+        let problem = match feature.geometry() {
+            None => {
+                self.null += 1;
+                GeometryProblem::Null
+            }
+            Some(g) => {
+                *self
+                    .types
+                    .entry(geometry_type_to_name(g.geometry_type()))
+                    .or_default() += 1;
+                if g.is_empty() {
+                    self.empty += 1;
+                    GeometryProblem::Empty
+                } else if let Some(invalidity) = unclosed_ring(g) {
+                    // Caught before the conversion, which closes the rings
+                    self.invalid += 1;
+                    GeometryProblem::Invalid(invalidity)
+                } else {
+                    match g.to_geo() {
+                        Err(_) => {
+                            self.unconvertible += 1;
+                            GeometryProblem::Unconvertible(g.geometry_name())
+                        }
+                        Ok(geometry) => match validate(&geometry) {
+                            Ok(()) => return,
+                            Err(invalidity) => {
+                                self.invalid += 1;
+                                GeometryProblem::Invalid(invalidity)
+                            }
+                        },
+                    }
+                }
+            }
+        };
+        if self.problems.len() < MAX_PROBLEMS {
+            self.problems.push(ProblemFeature {
+                fid: feature.fid(),
+                problem,
+            });
+        }
+    }
+
+    pub fn problem_count(&self) -> u64 {
+        self.null + self.empty + self.unconvertible + self.invalid
+    }
+
+    /// GeoJSON feature collection of the located problems, as points transformed by
+    /// `transform`.
+    pub fn error_points(&self, transform: &CoordTransform) -> Result<Value, GdalError> {
+        let mut features = vec![];
+        for p in &self.problems {
+            let GeometryProblem::Invalid(invalidity) = &p.problem else {
+                continue;
+            };
+            let (mut x, mut y) = ([invalidity.x], [invalidity.y]);
+            transform.transform_coords(&mut x, &mut y, &mut [0.])?;
+            features.push(json!({
+                "type": "Feature",
+                "properties": {"fid": p.fid, "reason": invalidity.reason},
+                "geometry": {"type": "Point", "coordinates": [x[0], y[0]]},
+            }));
+        }
+        Ok(json!({"type": "FeatureCollection", "features": features}))
+    }
+
+    /// Write the located problems of `layer` to `path` as GeoJSON points, returning how many
+    /// were written. Refused for a layer without a CRS, GeoJSON wanting longitude/latitude.
+    pub fn write_error_points(&self, layer: &Layer, path: &Path) -> Result<usize, String> {
+        let ssr = layer
+            .spatial_ref()
+            .ok_or("The layer has no CRS, its problems cannot be placed in GeoJSON")?;
+        let points = wgs84()
+            .and_then(|dsr| CoordTransform::new(&traditional_axis_order(&ssr), &dsr))
+            .and_then(|transform| self.error_points(&transform))
+            .map_err(|e| e.to_string())?;
+        let text = serde_json::to_string_pretty(&points).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| e.to_string())?;
+        Ok(points["features"].as_array().map_or(0, |f| f.len()))
+    }
+}
+
+/// Geometries of one geometry field of a layer as longitude/latitude, ready to be drawn.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct MapGeometries {
+    pub geom_field: usize,
+    /// FID and geometry of the first `MAX_DRAWN_GEOMETRIES` features, none without a CRS
+    #[serde(skip)]
+    pub features: Vec<(u64, geo::Geometry)>,
+    /// Number of features of the layer, filters applied
+    pub count: u64,
+}
+
+impl MapGeometries {
+    /// Read and reproject the geometries of `geom_field`, honouring the filters of `layer`.
+    pub fn read(layer: &mut Layer, geom_field: usize) -> Self {
+        let transform = layer
+            .defn()
+            .geom_fields()
+            .nth(geom_field)
+            .and_then(|g| g.spatial_ref().ok())
+            .and_then(|ssr| {
+                CoordTransform::new(&traditional_axis_order(&ssr), &wgs84().ok()?).ok()
+            });
+        let features = match transform {
+            Some(transform) => layer
+                .features()
+                .take(MAX_DRAWN_GEOMETRIES)
+                .filter_map(|f| {
+                    let geometry = f.geometry_by_index(geom_field).ok()?;
+                    let geometry = geometry.transform(&transform).ok()?.to_geo().ok()?;
+                    Some((f.fid()?, geometry))
+                })
+                .collect(),
+            None => vec![],
+        };
+        Self {
+            geom_field,
+            features,
+            count: layer.feature_count(),
+        }
+    }
+}
+
+/// First ring of a polygon not ending where it starts, located at its last vertex.
+fn unclosed_ring(geometry: &Geometry) -> Option<Invalidity> {
+    match unsafe { gdal_sys::OGR_GT_Flatten(geometry.geometry_type()) } {
+        OGRwkbGeometryType::wkbPolygon => (0..geometry.geometry_count()).find_map(|i| {
+            let ring = geometry.get_geometry(i).get_point_vec();
+            let (first, last) = (ring.first()?, ring.last()?);
+            ((first.0, first.1) != (last.0, last.1)).then(|| Invalidity {
+                reason: "Ring not closed".into(),
+                x: last.0,
+                y: last.1,
+            })
+        }),
+        OGRwkbGeometryType::wkbMultiPolygon | OGRwkbGeometryType::wkbGeometryCollection => {
+            (0..geometry.geometry_count()).find_map(|i| unclosed_ring(&geometry.get_geometry(i)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gdal::{Dataset, DriverManager, LayerOptions};
+
+    use super::*;
+
+    #[test]
+    fn test_map_geometries() {
+        // LV95 points around Lausanne
+        let dataset = Dataset::open("data/my_dataset.gpkg").unwrap();
+        let mut layer = dataset.layer_by_name("ms_envconst_dioxines").unwrap();
+        let map = MapGeometries::read(&mut layer, 0);
+        assert_eq!(map.count, layer.feature_count());
+        assert!(!map.features.is_empty());
+        for (_, geometry) in &map.features {
+            let geo::Geometry::Point(p) = geometry else {
+                panic!("{geometry:?}");
+            };
+            assert!(
+                (6.5..6.8).contains(&p.x()) && (46.4..46.7).contains(&p.y()),
+                "{p:?}"
+            );
+        }
+    }
+
+    fn summary() -> GeometrySummary {
+        GeometrySummary {
+            null: 1,
+            invalid: 1,
+            problems: vec![
+                ProblemFeature {
+                    fid: Some(3),
+                    problem: GeometryProblem::Null,
+                },
+                ProblemFeature {
+                    fid: Some(7),
+                    problem: GeometryProblem::Invalid(Invalidity {
+                        reason: "Ring self-intersection".into(),
+                        x: 1.,
+                        y: 2.,
+                    }),
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_error_points() {
+        let transform = CoordTransform::new(&wgs84().unwrap(), &wgs84().unwrap()).unwrap();
+        assert_eq!(
+            summary().error_points(&transform).unwrap(),
+            json!({
+                "type": "FeatureCollection",
+                "features": [{
+                    "type": "Feature",
+                    "properties": {"fid": 7, "reason": "Ring self-intersection"},
+                    "geometry": {"type": "Point", "coordinates": [1., 2.]},
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_error_points() {
+        let mut dataset = DriverManager::get_driver_by_name("Memory")
+            .unwrap()
+            .create_vector_only("")
+            .unwrap();
+        let path = std::env::temp_dir().join("vivitui_test_problems.geojson");
+        let layer = dataset
+            .create_layer(LayerOptions {
+                name: "no_crs",
+                ..Default::default()
+            })
+            .unwrap();
+        // GeoJSON is longitude/latitude, which a layer without a CRS cannot be placed in
+        assert!(summary().write_error_points(&layer, &path).is_err());
+        let srs = wgs84().unwrap();
+        let layer = dataset
+            .create_layer(LayerOptions {
+                name: "wgs84",
+                srs: Some(&srs),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(summary().write_error_points(&layer, &path), Ok(1));
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["features"][0]["properties"]["fid"], 7);
+        std::fs::remove_file(path).unwrap();
+    }
+}
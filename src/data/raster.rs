@@ -0,0 +1,76 @@
+use gdal::{raster::RasterBand, Dataset, Metadata};
+use serde::{Deserialize, Serialize};
+
+use super::{extent::Extent, srs::Srs};
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BandInfo {
+    pub index: isize,
+    pub data_type: String,
+    pub no_data: Option<f64>,
+    pub color_interpretation: String,
+    pub block_size: (usize, usize),
+    pub overviews: Vec<(usize, usize)>,
+    pub compression: Option<String>,
+}
+
+impl BandInfo {
+    fn new(index: isize, band: &RasterBand<'_>, compression: Option<String>) -> Self {
+        let overviews = (0..band.overview_count().unwrap_or(0))
+            .filter_map(|i| band.overview(i as isize).ok())
+            .map(|o| o.size())
+            .collect();
+        Self {
+            index,
+            data_type: band.band_type().name(),
+            no_data: band.no_data_value(),
+            color_interpretation: band.color_interpretation().name(),
+            block_size: band.block_size(),
+            overviews,
+            compression: band
+                .metadata_item("COMPRESSION", "IMAGE_STRUCTURE")
+                .or(compression),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RasterInfo {
+    pub size: (usize, usize),
+    pub geo_transform: Option<[f64; 6]>,
+    pub extent: Extent,
+    pub srs: Srs,
+    pub bands: Vec<BandInfo>,
+}
+
+impl RasterInfo {
+    /// Raster information of the dataset, `None` if it has no bands.
+    pub fn from_dataset(dataset: &Dataset) -> Option<Self> {
+        if dataset.raster_count() == 0 {
+            return None;
+        }
+        let size = dataset.raster_size();
+        let geo_transform = dataset.geo_transform().ok();
+        let compression = dataset.metadata_item("COMPRESSION", "IMAGE_STRUCTURE");
+        let bands = (1..=dataset.raster_count())
+            .filter_map(|i| {
+                dataset
+                    .rasterband(i)
+                    .ok()
+                    .map(|b| BandInfo::new(i, &b, compression.clone()))
+            })
+            .collect();
+        Some(Self {
+            size,
+            geo_transform,
+            extent: geo_transform
+                .map(|gt| Extent::from_geo_transform(&gt, size))
+                .unwrap_or_default(),
+            srs: dataset
+                .spatial_ref()
+                .map(|srs| Srs::from(&srs))
+                .unwrap_or_default(),
+            bands,
+        })
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    ffi::CString,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use gdal::{
+    errors::GdalError,
+    vector::{sql::Dialect, LayerAccess},
+    Dataset, DriverManager, Metadata,
+};
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+use super::gdal_message;
+
+/// Queries kept in the history file.
+pub const MAX_HISTORY: usize = 500;
+
+#[derive(Debug, Default, Display, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SqlDialect {
+    #[default]
+    #[strum(to_string = "OGR SQL")]
+    Ogr,
+    #[strum(to_string = "SQLite")]
+    Sqlite,
+}
+
+impl SqlDialect {
+    pub fn toggle(self) -> Self {
+        match self {
+            SqlDialect::Ogr => SqlDialect::Sqlite,
+            SqlDialect::Sqlite => SqlDialect::Ogr,
+        }
+    }
+}
+
+impl From<SqlDialect> for Dialect {
+    fn from(dialect: SqlDialect) -> Self {
+        match dialect {
+            SqlDialect::Ogr => Dialect::OGR,
+            SqlDialect::Sqlite => Dialect::SQLITE,
+        }
+    }
+}
+
+/// Run `query` on `dataset` and copy its result set to an in-memory dataset, so that it can be
+/// browsed like any other layer. `None` if the statement returns no result set.
+pub fn execute(
+    dataset: &Dataset,
+    query: &str,
+    dialect: SqlDialect,
+) -> Result<Option<Dataset>, String> {
+    gdal_message(|| {
+        let Some(result) = dataset.execute_sql(query, None, dialect.into())? else {
+            return Ok(None);
+        };
+        let mut copy = DriverManager::get_driver_by_name("Memory")?.create_vector_only("")?;
+        let name = CString::new(result.name())?;
+        let c_layer = unsafe {
+            gdal_sys::GDALDatasetCopyLayer(
+                copy.c_dataset(),
+                result.c_layer(),
+                name.as_ptr(),
+                std::ptr::null_mut(),
+            )
+        };
+        if c_layer.is_null() {
+            return Err(GdalError::NullPointer {
+                method_name: "GDALDatasetCopyLayer",
+                msg: String::new(),
+            });
+        }
+        copy.set_description(&format!("SQL: {query}"))?;
+        Ok(Some(copy))
+    })
+}
+
+/// Queries run in the SQL console, oldest first, saved one per line.
+#[derive(Debug, Default, Clone)]
+pub struct History {
+    path: PathBuf,
+    pub entries: Vec<String>,
+}
+
+impl History {
+    /// Read the history saved at `path`, empty if there is none yet.
+    pub fn load(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .map(|s| s.lines().map(String::from).collect())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    /// Add `query` to the history and save it.
+    pub fn push(&mut self, query: &str) -> io::Result<()> {
+        let query = query.replace('\n', " ");
+        if self.entries.last() == Some(&query) {
+            return Ok(());
+        }
+        self.entries.push(query);
+        if self.entries.len() > MAX_HISTORY {
+            self.entries.drain(..self.entries.len() - MAX_HISTORY);
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::File::create(&self.path)?;
+        for entry in &self.entries {
+            writeln!(file, "{entry}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let path = std::env::temp_dir().join(format!("vivitui-history-{}", std::process::id()));
+        let mut history = History::load(path.clone());
+        assert!(history.entries.is_empty());
+        history.push("SELECT * FROM roads").unwrap();
+        history.push("SELECT * FROM roads").unwrap();
+        history
+            .push("SELECT type,\nCOUNT(*) FROM roads GROUP BY type")
+            .unwrap();
+        let history = History::load(path.clone());
+        assert_eq!(
+            history.entries,
+            vec![
+                "SELECT * FROM roads",
+                "SELECT type, COUNT(*) FROM roads GROUP BY type"
+            ]
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
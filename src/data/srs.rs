@@ -1,11 +1,57 @@
-use gdal::vector::{Layer, LayerAccess};
+use std::{
+    ffi::{c_char, CStr},
+    ptr,
+};
+
+use gdal::{
+    cpl::CslStringList,
+    errors::GdalError,
+    spatial_ref::{CoordTransform, SpatialRef},
+    vector::{Layer, LayerAccess, ToGdal},
+};
+use gdal_sys::{OGRAxisOrientation, OGRErr, OSRAxisMappingStrategy};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Eq, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Srs {
     pub name: String,
+    /// Pretty WKT1
     pub wkt: String,
     pub proj4: String,
+    /// Pretty WKT2_2019, left out of the `info` output like the PROJJSON
+    #[serde(skip)]
+    pub wkt2: String,
+    #[serde(skip)]
+    pub projjson: String,
+    /// Authority and code, such as `EPSG:2056`
+    pub authority: Option<String>,
+    /// Geographic, projected, compound, vertical…
+    pub kind: String,
+    pub datum: Option<String>,
+    pub ellipsoid: Option<String>,
+    pub prime_meridian: Option<String>,
+    /// Axes in the order of the definition
+    pub axes: Vec<Axis>,
+    /// Unit of the horizontal axes
+    pub unit: Option<String>,
+    pub area_of_use: Option<AreaOfUse>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Axis {
+    pub name: String,
+    /// North, East, Up…
+    pub orientation: String,
+}
+
+/// Where a CRS is meant to be used, with its bounding box in degrees.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AreaOfUse {
+    pub name: String,
+    pub west: f64,
+    pub south: f64,
+    pub east: f64,
+    pub north: f64,
 }
 
 impl Srs {
@@ -14,17 +60,206 @@ impl Srs {
             .lines()
             .count()
     }
+
+    /// The CRS for GDAL, read from the WKT2 which keeps what WKT1 cannot express,
+    /// or from the WKT1 when the WKT2 is missing, as after deserializing.
+    pub fn spatial_ref(&self) -> Result<SpatialRef, GdalError> {
+        let wkt = if self.wkt2.is_empty() {
+            &self.wkt
+        } else {
+            &self.wkt2
+        };
+        Ok(traditional_axis_order(&SpatialRef::from_wkt(wkt)?))
+    }
+}
+
+/// `srs` taking coordinates easting or longitude first, whatever the order of its authority.
+/// GDAL 3 follows the authority, which puts latitude first for EPSG:4326.
+pub fn traditional_axis_order(srs: &SpatialRef) -> SpatialRef {
+    let srs = srs.clone();
+    srs.set_axis_mapping_strategy(OSRAxisMappingStrategy::OAMS_TRADITIONAL_GIS_ORDER);
+    srs
+}
+
+/// EPSG:4326 with longitude first, the order the position map works in.
+pub fn wgs84() -> Result<SpatialRef, GdalError> {
+    Ok(traditional_axis_order(&SpatialRef::from_epsg(4326)?))
+}
+
+/// CRS given by the user as an EPSG code, with or without the `EPSG:` prefix, WKT or PROJ
+/// string, with easting or longitude first.
+pub fn from_user_input(definition: &str) -> Result<SpatialRef, GdalError> {
+    let definition = definition.trim();
+    let srs = match definition.parse::<u32>() {
+        Ok(code) => SpatialRef::from_epsg(code)?,
+        Err(_) => SpatialRef::from_definition(definition)?,
+    };
+    Ok(traditional_axis_order(&srs))
+}
+
+/// `geometry` transformed by `transform`.
+pub fn reproject_geometry(
+    geometry: &geo::Geometry,
+    transform: &CoordTransform,
+) -> Result<geo::Geometry, GdalError> {
+    let mut geometry = geometry.to_gdal()?;
+    geometry.transform_inplace(transform)?;
+    geometry.to_geo()
+}
+
+fn kind(srs: &SpatialRef) -> &'static str {
+    if srs.is_compound() {
+        "Compound"
+    } else if srs.is_projected() {
+        "Projected"
+    } else if srs.is_geocentric() {
+        "Geocentric"
+    } else if srs.is_geographic() {
+        "Geographic"
+    } else if srs.is_vertical() {
+        "Vertical"
+    } else if srs.is_local() {
+        "Engineering"
+    } else {
+        "Unknown"
+    }
+}
+
+/// Axes of the whole CRS, which `SpatialRef::axis_name` cannot query.
+fn axes(srs: &SpatialRef) -> Vec<Axis> {
+    (0..srs.axes_count())
+        .filter_map(|i| {
+            let mut orientation = OGRAxisOrientation::OAO_Other;
+            let name =
+                unsafe { gdal_sys::OSRGetAxis(srs.to_c_hsrs(), ptr::null(), i, &mut orientation) };
+            if name.is_null() {
+                return None;
+            }
+            let orientation = unsafe { CStr::from_ptr(gdal_sys::OSRAxisEnumToName(orientation)) };
+            Some(Axis {
+                name: unsafe { CStr::from_ptr(name) }.to_string_lossy().into(),
+                orientation: orientation.to_string_lossy().into(),
+            })
+        })
+        .collect()
+}
+
+/// Multi-line WKT in the version given by `format`, such as `WKT2_2019`.
+fn to_wkt(srs: &SpatialRef, format: &str) -> Result<String, GdalError> {
+    let options = CslStringList::try_from(&[("FORMAT", format), ("MULTILINE", "YES")])?;
+    let mut c_wkt: *mut c_char = ptr::null_mut();
+    let c_options = options.as_ptr() as *const *const c_char;
+    let err = unsafe { gdal_sys::OSRExportToWktEx(srs.to_c_hsrs(), &mut c_wkt, c_options) };
+    let wkt = match c_wkt.is_null() {
+        true => String::new(),
+        false => unsafe { CStr::from_ptr(c_wkt) }.to_string_lossy().into(),
+    };
+    unsafe { gdal_sys::VSIFree(c_wkt.cast()) };
+    if err != OGRErr::OGRERR_NONE {
+        return Err(GdalError::OgrError {
+            err,
+            method_name: "OSRExportToWktEx",
+        });
+    }
+    Ok(wkt)
+}
+
+impl From<&SpatialRef> for Srs {
+    fn from(srs: &SpatialRef) -> Self {
+        let attribute = |node: &str| srs.get_attr_value(node, 0).ok().flatten();
+        let unit = if srs.is_geographic() {
+            srs.angular_units_name().ok()
+        } else if srs.is_compound() {
+            None
+        } else {
+            srs.linear_units_name().ok()
+        };
+        Self {
+            name: srs.name().unwrap_or_default(),
+            wkt: srs.to_pretty_wkt().unwrap_or_default(),
+            proj4: srs.to_proj4().unwrap_or_default(),
+            wkt2: to_wkt(srs, "WKT2_2019").unwrap_or_default(),
+            projjson: srs.to_projjson().unwrap_or_default(),
+            authority: srs.authority().ok(),
+            kind: kind(srs).into(),
+            datum: attribute("DATUM"),
+            ellipsoid: attribute("SPHEROID"),
+            prime_meridian: attribute("PRIMEM"),
+            axes: axes(srs),
+            unit,
+            area_of_use: srs.area_of_use().map(|a| AreaOfUse {
+                name: a.name,
+                west: a.west_lon_degree,
+                south: a.south_lat_degree,
+                east: a.east_lon_degree,
+                north: a.north_lat_degree,
+            }),
+        }
+    }
 }
 
 impl From<&Layer<'_>> for Srs {
     fn from(layer: &Layer<'_>) -> Self {
         match layer.spatial_ref() {
-            Some(srs) => Self {
-                name: srs.name().unwrap_or_default(),
-                wkt: srs.to_pretty_wkt().unwrap_or_default(),
-                proj4: srs.to_proj4().unwrap_or_default(),
-            },
+            Some(srs) => Self::from(&srs),
             None => Self::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// Amersfoort / RD New + NAP height
+    const COMPOUND: u32 = 7415;
+
+    fn orientations(srs: &SpatialRef) -> Vec<String> {
+        axes(srs).into_iter().map(|a| a.orientation).collect()
+    }
+
+    #[test]
+    fn test_kind() {
+        let kind_of = |code| kind(&SpatialRef::from_epsg(code).unwrap());
+        assert_eq!(kind_of(2056), "Projected");
+        assert_eq!(kind_of(4326), "Geographic");
+        assert_eq!(kind_of(COMPOUND), "Compound");
+    }
+
+    #[test]
+    fn test_axes() {
+        let lv95 = SpatialRef::from_epsg(2056).unwrap();
+        assert_eq!(orientations(&lv95), vec!["East", "North"]);
+        // In the order of the authority, latitude first
+        let wgs84 = SpatialRef::from_epsg(4326).unwrap();
+        assert_eq!(orientations(&wgs84), vec!["North", "East"]);
+        let compound = SpatialRef::from_epsg(COMPOUND).unwrap();
+        assert_eq!(orientations(&compound), vec!["East", "North", "Up"]);
+    }
+
+    #[test]
+    fn test_to_wkt() {
+        for (code, root) in [
+            (2056, "PROJCRS["),
+            (4326, "GEOGCRS["),
+            (COMPOUND, "COMPOUNDCRS["),
+        ] {
+            let wkt = to_wkt(&SpatialRef::from_epsg(code).unwrap(), "WKT2_2019").unwrap();
+            assert!(wkt.starts_with(root), "{wkt}");
+            assert!(wkt.lines().count() > 1, "{wkt}");
+        }
+        let wkt1 = to_wkt(&SpatialRef::from_epsg(2056).unwrap(), "WKT1").unwrap();
+        assert!(wkt1.starts_with("PROJCS[\"CH1903+ / LV95\""), "{wkt1}");
+    }
+
+    #[test]
+    fn test_spatial_ref() {
+        let mut srs = Srs::from(&SpatialRef::from_epsg(COMPOUND).unwrap());
+        assert_eq!(kind(&srs.spatial_ref().unwrap()), "Compound");
+        // Deserialized CRS have no WKT2
+        srs.wkt2.clear();
+        assert_eq!(srs.spatial_ref().unwrap().name().unwrap(), srs.name);
+    }
+}
@@ -0,0 +1,139 @@
+use geo::{
+    sweep::Intersections, Contains, Coord, CoordsIter, Geometry, LineIntersection, LineString,
+    Polygon, RemoveRepeatedPoints,
+};
+use serde::{Deserialize, Serialize};
+
+/// Why a geometry is invalid, and where.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Invalidity {
+    pub reason: String,
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Invalidity {
+    fn new(reason: &str, coord: Coord) -> Self {
+        Self {
+            reason: reason.into(),
+            x: coord.x,
+            y: coord.y,
+        }
+    }
+}
+
+/// Check `geometry` against the simple features rules, returning the first problem found.
+pub fn validate(geometry: &Geometry) -> Result<(), Invalidity> {
+    if let Some(c) = geometry
+        .coords_iter()
+        .find(|c| !c.x.is_finite() || !c.y.is_finite())
+    {
+        return Err(Invalidity::new("Non-finite coordinate", c));
+    }
+    match geometry {
+        Geometry::LineString(l) => validate_line_string(l),
+        Geometry::MultiLineString(ml) => ml.iter().try_for_each(validate_line_string),
+        Geometry::Polygon(p) => validate_polygon(p),
+        Geometry::MultiPolygon(mp) => mp.iter().try_for_each(validate_polygon),
+        Geometry::GeometryCollection(gc) => gc.iter().try_for_each(validate),
+        _ => Ok(()),
+    }
+}
+
+fn validate_line_string(line: &LineString) -> Result<(), Invalidity> {
+    match line.remove_repeated_points().0.as_slice() {
+        [] => Ok(()),
+        [c] => Err(Invalidity::new("Too few points", *c)),
+        _ => Ok(()),
+    }
+}
+
+fn validate_polygon(polygon: &Polygon) -> Result<(), Invalidity> {
+    validate_ring(polygon.exterior())?;
+    let shell = Polygon::new(polygon.exterior().clone(), vec![]);
+    for hole in polygon.interiors() {
+        validate_ring(hole)?;
+        if let Some(c) = hole.0.iter().find(|c| !shell.contains(*c)) {
+            if !shell.exterior().contains(c) {
+                return Err(Invalidity::new("Hole lies outside shell", *c));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_ring(ring: &LineString) -> Result<(), Invalidity> {
+    let ring = ring.remove_repeated_points();
+    let Some(first) = ring.0.first() else {
+        return Ok(());
+    };
+    if ring.0.len() < 4 {
+        return Err(Invalidity::new("Too few points", *first));
+    }
+    // Consecutive segments share a vertex, any other contact is a self-intersection
+    let adjacent = |a: &geo::Line, b: &geo::Line, c: Coord| {
+        (a.end == b.start || b.end == a.start) && (c == a.end || c == a.start)
+    };
+    for (a, b, intersection) in Intersections::from_iter(ring.lines()) {
+        match intersection {
+            LineIntersection::Collinear { intersection } => {
+                return Err(Invalidity::new(
+                    "Ring self-intersection",
+                    intersection.start,
+                ))
+            }
+            LineIntersection::SinglePoint { intersection, .. }
+                if !adjacent(&a, &b, intersection) =>
+            {
+                return Err(Invalidity::new("Ring self-intersection", intersection))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::{line_string, point, polygon};
+
+    use super::*;
+
+    #[test]
+    fn test_valid_polygon() {
+        let p = polygon![(x: 0., y: 0.), (x: 2., y: 0.), (x: 2., y: 2.), (x: 0., y: 2.)];
+        assert_eq!(validate(&p.into()), Ok(()));
+    }
+
+    #[test]
+    fn test_bow_tie() {
+        let p = polygon![(x: 0., y: 0.), (x: 2., y: 2.), (x: 2., y: 0.), (x: 0., y: 2.)];
+        let err = validate(&p.into()).unwrap_err();
+        assert_eq!(err.reason, "Ring self-intersection");
+        assert_eq!((err.x, err.y), (1., 1.));
+    }
+
+    #[test]
+    fn test_hole_outside_shell() {
+        let p = polygon!(
+            exterior: [(x: 0., y: 0.), (x: 2., y: 0.), (x: 2., y: 2.), (x: 0., y: 2.)],
+            interiors: [[(x: 5., y: 5.), (x: 6., y: 5.), (x: 6., y: 6.)]],
+        );
+        assert_eq!(
+            validate(&p.into()).unwrap_err().reason,
+            "Hole lies outside shell"
+        );
+    }
+
+    #[test]
+    fn test_degenerate() {
+        let l = line_string![(x: 1., y: 1.), (x: 1., y: 1.)];
+        assert_eq!(validate(&l.into()).unwrap_err().reason, "Too few points");
+        assert_eq!(
+            validate(&point!(x: f64::NAN, y: 0.).into())
+                .unwrap_err()
+                .reason,
+            "Non-finite coordinate"
+        );
+    }
+}
@@ -0,0 +1,470 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use gdal::{vector::LayerAccess, Dataset};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    crs,
+    diff::{FeatureDiff, FeatureKey, SchemaDiff},
+    export::{self, ExportOptions},
+    extent::Extent,
+    features::{FeatureInfo, FeaturePage},
+    geometries::{GeometrySummary, MapGeometries},
+    set_attribute_filter, set_spatial_filter,
+    sql::{self, SqlDialect},
+    DatasetInfo, LayerFilter, LayerInfo, SpatialFilter,
+};
+use crate::action::Action;
+
+/// Work for the GDAL thread.
+#[derive(Debug)]
+pub enum Request {
+    LoadLayer {
+        dataset: usize,
+        layer: usize,
+        cancel: CancellationToken,
+    },
+    ReadFeatures {
+        dataset: usize,
+        layer: usize,
+        offset: u64,
+    },
+    FindFeature {
+        dataset: usize,
+        layer: usize,
+        fid: u64,
+    },
+    ReadFeature {
+        dataset: usize,
+        layer: usize,
+        fid: u64,
+    },
+    ReadMapGeometries {
+        dataset: usize,
+        layer: usize,
+        geom_field: usize,
+    },
+    SetAttributeFilter {
+        dataset: usize,
+        layer: usize,
+        filter: Option<String>,
+    },
+    SetSpatialFilter {
+        dataset: usize,
+        layer: usize,
+        filter: Option<SpatialFilter>,
+    },
+    ExecuteSql {
+        dataset: usize,
+        query: String,
+        dialect: SqlDialect,
+    },
+    Export {
+        dataset: usize,
+        layer: usize,
+        options: ExportOptions,
+    },
+    WriteProblems {
+        dataset: usize,
+        layer: usize,
+        summary: GeometrySummary,
+        path: PathBuf,
+    },
+    DiffSchemas {
+        before: usize,
+        after: usize,
+        tolerance: f64,
+    },
+    DiffFeatures {
+        before: (usize, usize),
+        after: (usize, usize),
+        key: FeatureKey,
+        tolerance: f64,
+        cancel: CancellationToken,
+    },
+    LayersExtent,
+    ListCrs,
+}
+
+/// Owns the opened datasets on a dedicated thread, so that slow GDAL calls never block the
+/// UI. Results come back as actions.
+#[derive(Debug)]
+pub struct Worker {
+    tx: Sender<Request>,
+    current: CancellationToken,
+    /// Cancels the running feature diff
+    diff: CancellationToken,
+}
+
+impl Worker {
+    pub fn spawn(datasets: Vec<Dataset>, action_tx: UnboundedSender<Action>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || run(datasets, rx, action_tx));
+        Self {
+            tx,
+            current: CancellationToken::new(),
+            diff: CancellationToken::new(),
+        }
+    }
+
+    /// Load a layer in the background, cancelling the previous load if still running.
+    pub fn load_layer(&mut self, dataset: usize, layer: usize) {
+        self.cancel();
+        self.current = CancellationToken::new();
+        self.send(Request::LoadLayer {
+            dataset,
+            layer,
+            cancel: self.current.clone(),
+        });
+    }
+
+    /// Read a page of features starting from the `offset`-th one.
+    pub fn read_features(&self, dataset: usize, layer: usize, offset: u64) {
+        self.send(Request::ReadFeatures {
+            dataset,
+            layer,
+            offset,
+        });
+    }
+
+    /// Read the page of features starting with the feature `fid`.
+    pub fn find_feature(&self, dataset: usize, layer: usize, fid: u64) {
+        self.send(Request::FindFeature {
+            dataset,
+            layer,
+            fid,
+        });
+    }
+
+    /// Read everything about the feature `fid`.
+    pub fn read_feature(&self, dataset: usize, layer: usize, fid: u64) {
+        self.send(Request::ReadFeature {
+            dataset,
+            layer,
+            fid,
+        });
+    }
+
+    /// Filter a layer with an OGR SQL WHERE clause, or clear its filter with `None`.
+    pub fn set_attribute_filter(&self, dataset: usize, layer: usize, filter: Option<String>) {
+        self.send(Request::SetAttributeFilter {
+            dataset,
+            layer,
+            filter,
+        });
+    }
+
+    /// Keep only the features of a layer intersecting a box, or clear the box with `None`.
+    pub fn set_spatial_filter(&self, dataset: usize, layer: usize, filter: Option<SpatialFilter>) {
+        self.send(Request::SetSpatialFilter {
+            dataset,
+            layer,
+            filter,
+        });
+    }
+
+    /// Run a SQL statement on a dataset, its result set becoming a new in-memory dataset.
+    pub fn execute_sql(&self, dataset: usize, query: String, dialect: SqlDialect) {
+        self.send(Request::ExecuteSql {
+            dataset,
+            query,
+            dialect,
+        });
+    }
+
+    /// Write a layer, with its filters applied, to a new dataset.
+    pub fn export(&self, dataset: usize, layer: usize, options: ExportOptions) {
+        self.send(Request::Export {
+            dataset,
+            layer,
+            options,
+        });
+    }
+
+    /// Write the located geometry problems of a layer to a GeoJSON file.
+    pub fn write_problems(
+        &self,
+        dataset: usize,
+        layer: usize,
+        summary: GeometrySummary,
+        path: PathBuf,
+    ) {
+        self.send(Request::WriteProblems {
+            dataset,
+            layer,
+            summary,
+            path,
+        });
+    }
+
+    /// Compare the layers of two datasets.
+    pub fn diff_schemas(&self, before: usize, after: usize, tolerance: f64) {
+        self.send(Request::DiffSchemas {
+            before,
+            after,
+            tolerance,
+        });
+    }
+
+    /// Compare the features of two layers, given as dataset and layer index, cancelling the
+    /// previous comparison if still running.
+    pub fn diff_features(
+        &mut self,
+        before: (usize, usize),
+        after: (usize, usize),
+        key: FeatureKey,
+        tolerance: f64,
+    ) {
+        self.cancel_diff();
+        self.diff = CancellationToken::new();
+        self.send(Request::DiffFeatures {
+            before,
+            after,
+            key,
+            tolerance,
+            cancel: self.diff.clone(),
+        });
+    }
+
+    /// Extent of every opened dataset, as longitude/latitude.
+    pub fn layers_extent(&self) {
+        self.send(Request::LayersExtent);
+    }
+
+    /// Every CRS of the PROJ database.
+    pub fn list_crs(&self) {
+        self.send(Request::ListCrs);
+    }
+
+    /// Read the geometries of a geometry field as longitude/latitude, for the position map.
+    pub fn read_map_geometries(&self, dataset: usize, layer: usize, geom_field: usize) {
+        self.send(Request::ReadMapGeometries {
+            dataset,
+            layer,
+            geom_field,
+        });
+    }
+
+    fn send(&self, request: Request) {
+        if self.tx.send(request).is_err() {
+            log::error!("GDAL thread is gone, cannot handle request");
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.current.cancel();
+    }
+
+    pub fn cancel_diff(&self) {
+        self.diff.cancel();
+    }
+}
+
+fn run(mut datasets: Vec<Dataset>, rx: Receiver<Request>, action_tx: UnboundedSender<Action>) {
+    // Filters stay on the GDAL layers, they are kept here to be reported with the layer
+    let mut filters: HashMap<(usize, usize), LayerFilter> = HashMap::new();
+    while let Ok(request) = rx.recv() {
+        let action = match request {
+            Request::LoadLayer {
+                dataset,
+                layer,
+                cancel,
+            } => {
+                if cancel.is_cancelled() {
+                    continue;
+                }
+                match datasets[dataset].layer(layer as isize) {
+                    Ok(mut l) => match LayerInfo::load(&mut l, &cancel) {
+                        Some(layerinfo) => match filters.get(&(dataset, layer)) {
+                            Some(filter) if !filter.is_empty() => Action::LayerLoaded(
+                                dataset,
+                                layer,
+                                layerinfo.filtered(filter.clone()),
+                            ),
+                            _ => Action::LayerLoaded(dataset, layer, layerinfo),
+                        },
+                        None => continue,
+                    },
+                    Err(e) => Action::Error(format!("Failed to load layer: {e}")),
+                }
+            }
+            Request::ReadFeatures {
+                dataset,
+                layer,
+                offset,
+            } => match datasets[dataset]
+                .layer(layer as isize)
+                .and_then(|mut l| FeaturePage::read(&mut l, offset))
+            {
+                Ok(page) => Action::PassFeaturePage(dataset, layer, page),
+                Err(e) => Action::Error(format!("Failed to read features: {e}")),
+            },
+            Request::FindFeature {
+                dataset,
+                layer,
+                fid,
+            } => match datasets[dataset].layer(layer as isize) {
+                Ok(mut l) => match FeaturePage::feature_index(&mut l, fid) {
+                    Some(offset) => match FeaturePage::read(&mut l, offset) {
+                        Ok(page) => Action::PassFeaturePage(dataset, layer, page),
+                        Err(e) => Action::Error(format!("Failed to read features: {e}")),
+                    },
+                    None => Action::FeatureNotFound(fid),
+                },
+                Err(e) => Action::Error(format!("Failed to read features: {e}")),
+            },
+            Request::ReadFeature {
+                dataset,
+                layer,
+                fid,
+            } => match datasets[dataset].layer(layer as isize) {
+                Ok(l) => match l.feature(fid) {
+                    Some(f) => Action::PassFeatureInfo(FeatureInfo::new(&l, &f)),
+                    None => Action::FeatureNotFound(fid),
+                },
+                Err(e) => Action::Error(format!("Failed to read feature: {e}")),
+            },
+            Request::ReadMapGeometries {
+                dataset,
+                layer,
+                geom_field,
+            } => match datasets[dataset].layer(layer as isize) {
+                Ok(mut l) => Action::PassMapGeometries(
+                    dataset,
+                    layer,
+                    MapGeometries::read(&mut l, geom_field),
+                ),
+                Err(e) => Action::Error(format!("Failed to read geometries: {e}")),
+            },
+            Request::SetAttributeFilter {
+                dataset,
+                layer,
+                filter,
+            } => match datasets[dataset].layer(layer as isize) {
+                Ok(mut l) => match set_attribute_filter(&mut l, filter.as_deref()) {
+                    Ok(()) => {
+                        filters.entry((dataset, layer)).or_default().attribute = filter.clone();
+                        Action::AttributeFilterSet(dataset, layer, filter)
+                    }
+                    Err(e) => {
+                        // OGR drops the previous filter when a new one fails to compile
+                        let previous = filters
+                            .get(&(dataset, layer))
+                            .and_then(|f| f.attribute.as_deref());
+                        let _ = set_attribute_filter(&mut l, previous);
+                        Action::AttributeFilterFailed(dataset, layer, e)
+                    }
+                },
+                Err(e) => Action::Error(format!("Failed to filter layer: {e}")),
+            },
+            Request::SetSpatialFilter {
+                dataset,
+                layer,
+                filter,
+            } => match datasets[dataset].layer(layer as isize) {
+                Ok(mut l) => {
+                    set_spatial_filter(&mut l, filter.as_ref());
+                    filters.entry((dataset, layer)).or_default().spatial = filter.clone();
+                    Action::SpatialFilterSet(dataset, layer, filter)
+                }
+                Err(e) => Action::Error(format!("Failed to filter layer: {e}")),
+            },
+            Request::ExecuteSql {
+                dataset,
+                query,
+                dialect,
+            } => match sql::execute(&datasets[dataset], &query, dialect) {
+                Ok(Some(result)) => {
+                    let datasetinfo = DatasetInfo::from(&result);
+                    datasets.push(result);
+                    Action::SqlResult(Some(datasetinfo))
+                }
+                Ok(None) => Action::SqlResult(None),
+                Err(e) => Action::SqlFailed(e),
+            },
+            Request::Export {
+                dataset,
+                layer,
+                options,
+            } => {
+                let filter = filters.get(&(dataset, layer)).cloned().unwrap_or_default();
+                // Report whole percents only, GDAL calls back for every feature
+                let mut percent = None;
+                let progress = |complete: f64| {
+                    let p = (complete * 100.) as u16;
+                    if percent != Some(p) {
+                        percent = Some(p);
+                        let _ = action_tx.send(Action::ExportProgress(complete));
+                    }
+                };
+                match export::export(&datasets[dataset], layer, &filter, &options, progress) {
+                    Ok(()) => Action::ExportDone(options.path),
+                    Err(e) => Action::ExportFailed(e),
+                }
+            }
+            Request::WriteProblems {
+                dataset,
+                layer,
+                summary,
+                path,
+            } => match datasets[dataset]
+                .layer(layer as isize)
+                .map_err(|e| e.to_string())
+                .and_then(|l| summary.write_error_points(&l, &path))
+            {
+                Ok(n) => Action::ProblemsWritten(path, n),
+                Err(e) => Action::WriteProblemsFailed(e),
+            },
+            Request::DiffSchemas {
+                before,
+                after,
+                tolerance,
+            } => Action::PassSchemaDiff(SchemaDiff::from_datasets(
+                &datasets[before],
+                &datasets[after],
+                &[],
+                tolerance,
+            )),
+            Request::DiffFeatures {
+                before,
+                after,
+                key,
+                tolerance,
+                cancel,
+            } => {
+                if cancel.is_cancelled() {
+                    continue;
+                }
+                let layers = datasets[before.0]
+                    .layer(before.1 as isize)
+                    .and_then(|a| Ok((a, datasets[after.0].layer(after.1 as isize)?)));
+                match layers {
+                    Ok((mut a, mut b)) => {
+                        match FeatureDiff::from_layers(&mut a, &mut b, key, tolerance, &cancel) {
+                            Ok(Some(diff)) => Action::PassFeatureDiff(diff),
+                            Ok(None) => continue,
+                            Err(e) => Action::FeatureDiffFailed(e),
+                        }
+                    }
+                    Err(e) => Action::FeatureDiffFailed(e.to_string()),
+                }
+            }
+            Request::LayersExtent => Action::PassLayersExtent(
+                datasets
+                    .iter()
+                    .filter_map(Extent::of_dataset)
+                    .reduce(|a, b| a.union(&b)),
+            ),
+            Request::ListCrs => Action::PassCrsList(crs::list()),
+        };
+        if action_tx.send(action).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+
+use crate::data::{self, diff::SchemaDiff, GdalOptions};
+
+/// Print the schema differences between the datasets at `before` and `after` to stdout as JSON.
+pub fn run(
+    before: &Path,
+    after: &Path,
+    gdal_options: &GdalOptions,
+    tolerance: f64,
+    layers: &[String],
+) -> Result<()> {
+    let open = |path: &Path| {
+        data::dataset(path.to_path_buf(), gdal_options)
+            .wrap_err_with(|| format!("Unable to open dataset {}", path.display()))
+    };
+    let diff = SchemaDiff::from_datasets(&open(before)?, &open(after)?, layers, tolerance);
+    println!("{}", serde_json::to_string_pretty(&diff)?);
+    Ok(())
+}
@@ -0,0 +1,336 @@
+use std::{fmt::Write, path::Path};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use gdal::{vector::LayerAccess, Dataset};
+use serde::Serialize;
+use yaml_rust2::{Yaml, YamlEmitter};
+
+use crate::{
+    cli::InfoFormat,
+    data::{self, fields::FieldDefinition, raster::RasterInfo, GdalOptions, LayerInfo},
+};
+
+/// What `vivitui info` reports about a dataset.
+#[derive(Debug, Serialize)]
+pub struct DatasetReport {
+    pub path: String,
+    pub driver: String,
+    pub layers: Vec<LayerInfo>,
+    pub raster: Option<RasterInfo>,
+    /// Whether every feature was read for field statistics and geometry problems
+    #[serde(skip)]
+    pub scanned: bool,
+}
+
+impl DatasetReport {
+    /// Describe every layer of `dataset`, or only those named in `layers` if any, reading all
+    /// their features only if `scan` is set.
+    pub fn new(path: &Path, dataset: &Dataset, layers: &[String], scan: bool) -> Result<Self> {
+        let names: Vec<String> = dataset.layers().map(|l| l.name()).collect();
+        if let Some(missing) = layers.iter().find(|l| !names.contains(l)) {
+            return Err(eyre!("No layer named '{missing}' in {}", path.display()));
+        }
+        let layers = dataset
+            .layers()
+            .filter(|l| layers.is_empty() || layers.contains(&l.name()))
+            .map(|mut l| {
+                if scan {
+                    LayerInfo::from(&mut l)
+                } else {
+                    LayerInfo::metadata(&l)
+                }
+            })
+            .collect();
+        Ok(Self {
+            path: path.display().to_string(),
+            driver: dataset.driver().short_name(),
+            layers,
+            raster: RasterInfo::from_dataset(dataset),
+            scanned: scan,
+        })
+    }
+
+    /// The report as JSON, without the statistics left empty when the features were not read.
+    fn value(&self) -> Result<serde_json::Value> {
+        let mut value = serde_json::to_value(self)?;
+        if !self.scanned {
+            for layer in value["layers"].as_array_mut().into_iter().flatten() {
+                if let Some(fields) = layer["fields"].as_object_mut() {
+                    fields.remove("stats");
+                }
+                if let Some(layer) = layer.as_object_mut() {
+                    layer.remove("geometry_summary");
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    pub fn render(&self, format: InfoFormat) -> Result<String> {
+        Ok(match format {
+            InfoFormat::Json => serde_json::to_string_pretty(&self.value()?)?,
+            InfoFormat::Yaml => {
+                let mut out = String::new();
+                YamlEmitter::new(&mut out).dump(&to_yaml(self.value()?))?;
+                out
+            }
+            InfoFormat::Markdown => self.markdown(),
+            InfoFormat::Text => self.text(),
+        })
+    }
+
+    /// Same layout as `ogrinfo -so -al`.
+    fn text(&self) -> String {
+        let mut s = String::new();
+        let _ = writeln!(s, "INFO: Open of `{}'", self.path);
+        let _ = writeln!(s, "      using driver `{}' successful.", self.driver);
+        for layer in &self.layers {
+            let _ = writeln!(s);
+            let _ = writeln!(s, "Layer name: {}", layer.name);
+            let geom_fields = &layer.fields.geom_fields;
+            for g in geom_fields {
+                let _ = match geom_fields.len() {
+                    1 => writeln!(s, "Geometry: {}", g.geometry_type),
+                    _ => writeln!(s, "Geometry ({}): {}", g.name, g.geometry_type),
+                };
+            }
+            let _ = writeln!(s, "Feature Count: {}", layer.feature_number);
+            for g in &layer.geom_fields {
+                // Like ogrinfo, which leaves out the extent of layers without geometries
+                let Some(e) = &g.extent else {
+                    continue;
+                };
+                let _ = match layer.geom_fields.len() {
+                    1 => write!(s, "Extent: "),
+                    _ => write!(s, "Extent ({}): ", g.name),
+                };
+                let _ = writeln!(
+                    s,
+                    "({:.6}, {:.6}) - ({:.6}, {:.6})",
+                    e.xmin, e.ymin, e.xmax, e.ymax
+                );
+            }
+            for g in &layer.geom_fields {
+                let _ = match layer.geom_fields.len() {
+                    1 => writeln!(s, "Layer SRS WKT:"),
+                    _ => writeln!(s, "SRS WKT ({}):", g.name),
+                };
+                let _ = match g.srs.wkt.as_str() {
+                    "" => writeln!(s, "(unknown)"),
+                    wkt => writeln!(s, "{wkt}"),
+                };
+            }
+            if let [g] = geom_fields.as_slice() {
+                if !g.name.is_empty() {
+                    let _ = writeln!(s, "Geometry Column = {}", g.name);
+                }
+            }
+            for f in &layer.fields.fields {
+                let _ = writeln!(s, "{}", field_text(f));
+            }
+        }
+        if let Some(r) = &self.raster {
+            let _ = writeln!(s);
+            let _ = writeln!(s, "Size is {}, {}", r.size.0, r.size.1);
+            let e = &r.extent;
+            let _ = writeln!(
+                s,
+                "Extent: ({:.6}, {:.6}) - ({:.6}, {:.6})",
+                e.xmin, e.ymin, e.xmax, e.ymax
+            );
+            for b in &r.bands {
+                let _ = write!(
+                    s,
+                    "Band {} Block={}x{} Type={}, ColorInterp={}",
+                    b.index, b.block_size.0, b.block_size.1, b.data_type, b.color_interpretation
+                );
+                if let Some(no_data) = b.no_data {
+                    let _ = write!(s, ", NoData Value={no_data}");
+                }
+                let _ = writeln!(s);
+            }
+        }
+        s
+    }
+
+    fn markdown(&self) -> String {
+        let mut s = String::new();
+        let _ = writeln!(s, "# {}", self.path);
+        let _ = writeln!(s);
+        let _ = writeln!(s, "Driver: `{}`", self.driver);
+        for layer in &self.layers {
+            let _ = writeln!(s);
+            let _ = writeln!(s, "## {}", layer.name);
+            let _ = writeln!(s);
+            let _ = writeln!(s, "| Property | Value |");
+            let _ = writeln!(s, "|---|---|");
+            let _ = writeln!(s, "| Features | {} |", layer.feature_number);
+            for (g, definition) in layer.geom_fields.iter().zip(&layer.fields.geom_fields) {
+                let name = match g.name.as_str() {
+                    "" => String::new(),
+                    name => format!(" `{name}`"),
+                };
+                let _ = writeln!(s, "| Geometry{name} | {} |", definition.geometry_type);
+                let _ = match &g.extent {
+                    Some(e) => writeln!(
+                        s,
+                        "| Extent{name} | {}, {}, {}, {} |",
+                        e.xmin, e.ymin, e.xmax, e.ymax
+                    ),
+                    None => writeln!(s, "| Extent{name} | none |"),
+                };
+                let _ = writeln!(s, "| CRS{name} | {} |", g.srs.name);
+            }
+            if layer.fields.fields.is_empty() {
+                continue;
+            }
+            let _ = writeln!(s);
+            let _ = writeln!(
+                s,
+                "| Field | Type | Width | Precision | Nullable | Unique |"
+            );
+            let _ = writeln!(s, "|---|---|---|---|---|---|");
+            for f in &layer.fields.fields {
+                let type_name = match &f.subtype {
+                    Some(subtype) => format!("{} ({subtype})", f.type_name),
+                    None => f.type_name.clone(),
+                };
+                let _ = writeln!(
+                    s,
+                    "| {} | {type_name} | {} | {} | {} | {} |",
+                    f.name,
+                    f.width,
+                    f.precision,
+                    if f.nullable { "yes" } else { "no" },
+                    if f.unique { "yes" } else { "no" },
+                );
+            }
+        }
+        if let Some(r) = &self.raster {
+            let _ = writeln!(s);
+            let _ = writeln!(s, "## Raster");
+            let _ = writeln!(s);
+            let _ = writeln!(s, "Size: {} x {}", r.size.0, r.size.1);
+            let _ = writeln!(s);
+            let _ = writeln!(s, "| Band | Type | Color | No data |");
+            let _ = writeln!(s, "|---|---|---|---|");
+            for b in &r.bands {
+                let no_data = b.no_data.map(|n| n.to_string()).unwrap_or_default();
+                let _ = writeln!(
+                    s,
+                    "| {} | {} | {} | {no_data} |",
+                    b.index, b.data_type, b.color_interpretation
+                );
+            }
+        }
+        s
+    }
+}
+
+/// A field the way `ogrinfo` lists it, such as `name: String (80.0) NOT NULL`.
+fn field_text(f: &FieldDefinition) -> String {
+    let mut s = match &f.subtype {
+        Some(subtype) => format!("{}: {}({subtype})", f.name, f.type_name),
+        None => format!("{}: {}", f.name, f.type_name),
+    };
+    let _ = write!(s, " ({}.{})", f.width, f.precision);
+    if !f.nullable {
+        s.push_str(" NOT NULL");
+    }
+    if f.unique {
+        s.push_str(" UNIQUE");
+    }
+    if let Some(default) = &f.default {
+        let _ = write!(s, " DEFAULT {default}");
+    }
+    s
+}
+
+fn to_yaml(value: serde_json::Value) -> Yaml {
+    use serde_json::Value;
+    match value {
+        Value::Null => Yaml::Null,
+        Value::Bool(b) => Yaml::Boolean(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Yaml::Integer(i),
+            None => Yaml::Real(n.to_string()),
+        },
+        Value::String(s) => Yaml::String(s),
+        Value::Array(a) => Yaml::Array(a.into_iter().map(to_yaml).collect()),
+        Value::Object(o) => Yaml::Hash(
+            o.into_iter()
+                .map(|(k, v)| (Yaml::String(k), to_yaml(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Print the report of the dataset at `path` to stdout.
+pub fn run(
+    path: &Path,
+    gdal_options: &GdalOptions,
+    format: InfoFormat,
+    layers: &[String],
+    scan: bool,
+) -> Result<()> {
+    let dataset = data::dataset(path.to_path_buf(), gdal_options)
+        .wrap_err_with(|| format!("Unable to open dataset {}", path.display()))?;
+    let report = DatasetReport::new(path, &dataset, layers, scan)?;
+    println!("{}", report.render(format)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_text() {
+        let f = FieldDefinition {
+            name: "name".into(),
+            type_name: "String".into(),
+            width: 80,
+            nullable: false,
+            default: Some("'unknown'".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            field_text(&f),
+            "name: String (80.0) NOT NULL DEFAULT 'unknown'"
+        );
+        let f = FieldDefinition {
+            name: "flag".into(),
+            type_name: "Integer".into(),
+            subtype: Some("Boolean".into()),
+            nullable: true,
+            ..Default::default()
+        };
+        assert_eq!(field_text(&f), "flag: Integer(Boolean) (0.0)");
+    }
+
+    #[test]
+    fn test_report_without_scan() {
+        let path = Path::new("data/my_dataset.gpkg");
+        let dataset = Dataset::open(path).unwrap();
+        let layers = ["us_data".to_string()];
+        let report = DatasetReport::new(path, &dataset, &layers, false).unwrap();
+        let value = report.value().unwrap();
+        let layer = &value["layers"][0];
+        assert_eq!(layer["name"], "us_data");
+        assert!(layer["feature_number"].as_u64().unwrap() > 0);
+        assert!(layer.get("geometry_summary").is_none());
+        assert!(layer["fields"].get("stats").is_none());
+        let report = DatasetReport::new(path, &dataset, &layers, true).unwrap();
+        assert!(report.value().unwrap()["layers"][0]
+            .get("geometry_summary")
+            .is_some());
+    }
+
+    #[test]
+    fn test_to_yaml() {
+        let value = serde_json::json!({"name": "roads", "count": 3, "extent": [0.5, 1]});
+        let mut out = String::new();
+        YamlEmitter::new(&mut out).dump(&to_yaml(value)).unwrap();
+        assert_eq!(out, "---\nname: roads\ncount: 3\nextent:\n  - 0.5\n  - 1");
+    }
+}
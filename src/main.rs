@@ -8,16 +8,19 @@ pub mod cli;
 pub mod components;
 pub mod config;
 pub mod data;
+pub mod diff;
+pub mod info;
 pub mod mode;
 pub mod tui;
 pub mod utils;
 
 use clap::Parser;
-use cli::Cli;
-use color_eyre::eyre::Result;
+use cli::{Cli, Command};
+use color_eyre::eyre::{Result, WrapErr};
 
 use crate::{
     app::App,
+    config::Config,
     utils::{initialize_logging, initialize_panic_handler, version},
 };
 
@@ -27,8 +30,33 @@ async fn tokio_main() -> Result<()> {
     initialize_panic_handler()?;
 
     let args = Cli::parse();
-    let dataset = data::dataset("data/my_dataset.gpkg".into()).unwrap();
-    let mut app = App::new(dataset)?;
+    let config = Config::new()?;
+    let gdal_options = config.config.gdal.clone().merge(args.gdal_options());
+    gdal_options.apply_config_options()?;
+    match &args.command {
+        Some(Command::Info {
+            path,
+            format,
+            layers,
+            scan,
+        }) => return info::run(path, &gdal_options, *format, layers, *scan),
+        Some(Command::Diff {
+            before,
+            after,
+            tolerance,
+            layers,
+        }) => return diff::run(before, after, &gdal_options, *tolerance, layers),
+        None => {}
+    }
+    let datasets = args
+        .datasets
+        .iter()
+        .map(|p| {
+            data::dataset(p.clone(), &gdal_options)
+                .wrap_err_with(|| format!("Unable to open dataset {}", p.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut app = App::new(config, datasets)?;
     app.run().await?;
 
     Ok(())
@@ -8,4 +8,13 @@ pub enum Mode {
     Srs,
     Extent,
     Fields,
+    Geometries,
+    Raster,
+    PositionMap,
+    AttributeTable,
+    FeatureDetail,
+    SchemaDiff,
+    FeatureDiff,
+    /// A component is reading text, keys are not mapped to actions
+    Input,
 }